@@ -145,29 +145,25 @@ fn read_l6bundle(chunk: &Chunk, _data: &[u8]) -> Result<DecoderResult, Error> {
         _ => unreachable!()
     };
 
-    let mut banks = vec![];
+    let mut bundle = L6Bundle { bundle_type, ..Default::default() };
     for (type_id, chunk) in chunk.all_chunks() {
         match type_id {
-            types::HEAD => {
-                // not actually using head chunk for anything
-                read_head(chunk, false)?;
-            },
+            types::HEAD => { bundle.version = read_head(chunk, false)?.version; },
+            types::UNFO => { bundle.meta = Some(read_meta_tags(chunk)?); },
+            types::PINF => { bundle.target_device = Some(read_target_device(chunk, false)?); },
             types::BANK if bundle_type == BundleType::Bundle => {
                 let bank = read_bank(chunk, false)?;
-                banks.push(bank);
+                bundle.banks.push(bank);
             }
             types::FLDR if bundle_type == BundleType::Collection => {
                 let bank = read_bank(chunk, false)?;
-                banks.push(bank);
+                bundle.banks.push(bank);
             }
-            //types::UNFO => { patch.meta = read_meta_tags(chunk)?; },
-            //types::PINF => { patch.target_device = read_target_device(chunk, little_endian)?; },
             _ => {}
         }
     }
 
-    let batch = L6Bundle { bundle_type, banks };
-    Ok(DecoderResult::Bundle(batch))
+    Ok(DecoderResult::Bundle(bundle))
 }
 
 fn read_sounddiver_lib(chunk: &Chunk, data: &[u8]) -> Result<DecoderResult, Error> {
@@ -194,7 +190,8 @@ fn read_sounddiver_lib(chunk: &Chunk, data: &[u8]) -> Result<DecoderResult, Erro
         }
     }
 
-    todo!()
+    // patches of SoundDiver libs aren't decoded yet
+    Err(Error::FormatNotSupported())
 }
 
 
@@ -252,12 +249,15 @@ fn read_head(chunk: &Chunk, little_endian: bool) -> Result<BatchHead, io::Error>
     Ok(BatchHead { version })
 }
 
+/// Read a bank of a bundle or a folder of a collection, including its
+/// nested folders. Unknown chunks are kept in `unknown_chunks`.
 fn read_bank(chunk: &Chunk, little_endian: bool) -> Result<Bank, Error> {
     let mut bank = Bank::default();
     for (type_id, chunk) in chunk.all_chunks() {
         match type_id {
             types::BINF => {
                 let bank_info = read_bank_info(chunk, little_endian)?;
+                bank.info = bank_info.info;
                 bank.name = bank_info.name;
             }
             types::L6PA | types::L6AS | types::L6FS => {
@@ -271,10 +271,13 @@ fn read_bank(chunk: &Chunk, little_endian: bool) -> Result<Bank, Error> {
                 bank.patches.push(patch);
 
             }
-            _ => {
-                println!("{:?}", type_id);
-                return Err(io::Error::new(io::ErrorKind::InvalidData, "chunk not supported").into());
+            types::FLDR => {
+                bank.folders.push(read_bank(chunk, little_endian)?);
+            }
+            types::UNFO => {
+                bank.meta = Some(read_meta_tags(chunk)?);
             }
+            _ => bank.unknown_chunks.push(chunk.clone())
         }
     }
 
@@ -290,10 +293,10 @@ fn read_bank_info(chunk: &Chunk, little_endian: bool) -> Result<BankInfo, io::Er
     if data.len() != 68 { return Err(io::Error::new(io::ErrorKind::InvalidInput, "Incorrect chunk length")); }
 
     let mut r = reader_for_slice(data, little_endian);
-    r.read_u32()?;
+    let info = r.read_u32()?;
     let name = r.read_utf(64)?;
 
-    Ok(BankInfo { name })
+    Ok(BankInfo { info, name })
 }
 
 fn read_target_device(chunk: &Chunk, little_endian: bool) -> Result<TargetDevice, io::Error> {
@@ -87,11 +87,37 @@ impl Encoder {
     }
 
     pub fn write_with_endian(patch: &L6Patch, little_endian: bool) -> Result<Vec<u8>, io::Error> {
-        let mut envelope = Chunk::create(types::FORM, types::L6PA, little_endian);
+        let envelope = write_patch(patch, little_endian)?;
 
-        envelope.append_chunk(write_target_device(&patch.target_device, little_endian)?);
-        envelope.append_chunk(write_models(&patch.models, little_endian)?);
-        envelope.append_chunk(write_meta_tags(&patch.meta, little_endian)?);
+        let mut vec = Vec::new();
+        envelope.write(&mut vec)?;
+        Ok(vec)
+    }
+
+    pub fn write_bundle(bundle: &L6Bundle) -> Result<Vec<u8>, io::Error> {
+        Self::write_bundle_with_endian(bundle, false)
+    }
+
+    /// Write a bundle (L6B) or a collection (L6C), depending on the
+    /// bundle type. Banks of collections are written as folders,
+    /// including their nested folders.
+    pub fn write_bundle_with_endian(bundle: &L6Bundle, little_endian: bool) -> Result<Vec<u8>, io::Error> {
+        let (type_id, bank_id) = match bundle.bundle_type {
+            BundleType::Bundle => (types::L6BA, types::BANK),
+            BundleType::Collection => (types::L6CO, types::FLDR)
+        };
+        let mut envelope = Chunk::create(types::FORM, type_id, little_endian);
+
+        envelope.append_chunk(write_head(bundle.version, little_endian)?);
+        if let Some(target_device) = &bundle.target_device {
+            envelope.append_chunk(write_target_device(target_device, little_endian)?);
+        }
+        if let Some(meta) = &bundle.meta {
+            envelope.append_chunk(write_meta_tags(meta, little_endian)?);
+        }
+        for bank in &bundle.banks {
+            envelope.append_chunk(write_bank(bank, bank_id, little_endian)?);
+        }
 
         let mut vec = Vec::new();
         envelope.write(&mut vec)?;
@@ -99,6 +125,60 @@ impl Encoder {
     }
 }
 
+fn write_patch(patch: &L6Patch, little_endian: bool) -> Result<Chunk, io::Error> {
+    let type_id = match patch.patch_type {
+        PatchType::Patch => types::L6PA,
+        PatchType::AmpSetup => types::L6AS,
+        PatchType::FxSetup => types::L6FS
+    };
+    let mut envelope = Chunk::create(types::FORM, type_id, little_endian);
+
+    envelope.append_chunk(write_target_device(&patch.target_device, little_endian)?);
+    envelope.append_chunk(write_models(&patch.models, little_endian)?);
+    envelope.append_chunk(write_meta_tags(&patch.meta, little_endian)?);
+
+    Ok(envelope)
+}
+
+fn write_head(version: u32, little_endian: bool) -> Result<Chunk, io::Error> {
+    let mut data = [0u8; 4];
+    let mut w = writer_for_slice(&mut data, little_endian);
+
+    w.write_u32(version)?;
+
+    Ok(Chunk::Data { id: types::HEAD, data: Vec::from(data), little_endian })
+}
+
+fn write_bank(bank: &Bank, type_id: TypeID, little_endian: bool) -> Result<Chunk, io::Error> {
+    let mut envelope = Chunk::create(types::LIST, type_id, little_endian);
+
+    envelope.append_chunk(write_bank_info(bank, little_endian)?);
+    if let Some(meta) = &bank.meta {
+        envelope.append_chunk(write_meta_tags(meta, little_endian)?);
+    }
+    for patch in &bank.patches {
+        envelope.append_chunk(write_patch(patch, little_endian)?);
+    }
+    for folder in &bank.folders {
+        envelope.append_chunk(write_bank(folder, types::FLDR, little_endian)?);
+    }
+    for chunk in &bank.unknown_chunks {
+        envelope.append_chunk(chunk.clone());
+    }
+
+    Ok(envelope)
+}
+
+fn write_bank_info(bank: &Bank, little_endian: bool) -> Result<Chunk, io::Error> {
+    let mut data = [0u8; 68];
+    let mut w = writer_for_slice(&mut data, little_endian);
+
+    w.write_u32(bank.info)?;
+    w.write_utf(64, &bank.name)?;
+
+    Ok(Chunk::Data { id: types::BINF, data: Vec::from(data), little_endian })
+}
+
 fn write_target_device(dev: &TargetDevice, little_endian: bool) -> Result<Chunk, io::Error> {
     let mut data = [0u8; 76];
     let mut w = writer_for_slice(&mut data, little_endian);
@@ -215,7 +295,12 @@ fn write_model_param(param: &ModelParam, little_endian: bool) -> Result<Chunk, i
 
 #[cfg(test)]
 mod test {
-    use crate::encoder::{writer_for_vec};
+    use crate::decoder::{Decoder, DecoderResult};
+    use crate::encoder::{writer_for_vec, Encoder};
+    use crate::iff::Chunk;
+    use crate::model::{Bank, BundleType, L6Bundle, TargetDevice};
+    use crate::types::TypeID;
+    use crate::random::{random_meta, random_patch, Rng};
 
     #[test]
     fn test_write_utf() {
@@ -240,4 +325,48 @@ mod test {
 
         assert_eq!(&vec, expected);
     }
-}
\ No newline at end of file
+
+    #[test]
+    fn test_rng_zero_state() {
+        // the seed that mixes to a zero state
+        let mut rng = Rng::new(0x9e3779b97f4a7c15);
+        assert!((0..4).any(|_| rng.next_u64() != 0));
+    }
+
+    #[test]
+    fn test_write_collection() {
+        let mut rng = Rng::new(44);
+        let folder = |name: &str, folders: Vec<Bank>, rng: &mut Rng| Bank {
+            name: name.into(),
+            patches: (0..2).map(|_| random_patch(rng, 0x030002)).collect(),
+            folders,
+            meta: Some(random_meta(rng)),
+            ..Default::default()
+        };
+        let song = folder("Comfortably Numb", vec![], &mut rng);
+        let mut album = folder("The Wall", vec![song], &mut rng);
+        // unknown chunks and the bank info word are written back as read
+        album.info = 7;
+        album.unknown_chunks.push(Chunk::Data { id: TypeID(*b"XTRA"), data: vec![1, 2, 3, 4], little_endian: false });
+        let artist = folder("Pink Floyd", vec![album], &mut rng);
+        let collection = L6Bundle {
+            bundle_type: BundleType::Collection,
+            version: 3,
+            target_device: Some(TargetDevice { midi_id: 0x030002, name: "PODxt".into(), version: 0x0300 }),
+            meta: Some(random_meta(&mut rng)),
+            banks: vec![artist, Bank { name: "Empty".into(), ..Default::default() }]
+        };
+
+        let data = Encoder::write_bundle(&collection).unwrap();
+        let DecoderResult::Bundle(read) = Decoder::read(&data).unwrap() else {
+            panic!("expected a bundle");
+        };
+        assert_eq!(read, collection);
+
+        let names = read.banks.into_iter()
+            .flat_map(|b| b.into_flat())
+            .map(|b| b.name)
+            .collect::<Vec<_>>();
+        assert_eq!(names, vec!["Pink Floyd", "Pink Floyd/The Wall", "Pink Floyd/The Wall/Comfortably Numb", "Empty"]);
+    }
+}
@@ -6,6 +6,7 @@ use std::io::{Error, ErrorKind, Result, Write};
 
 use crate::types::{TypeID, UNALIGNED_CHUNKS};
 
+#[derive(Clone, PartialEq)]
 pub enum Chunk {
     Envelope {
         envelope_id: TypeID,
@@ -3,6 +3,8 @@ pub mod types;
 pub mod decoder;
 pub mod encoder;
 pub mod model;
+#[cfg(any(test, feature = "testing"))]
+pub mod random;
 
 mod bytecast;
 mod hex;
@@ -1,6 +1,7 @@
 use std::fmt::{Display, Formatter, Result};
+use crate::iff::Chunk;
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct TargetDevice {
     pub midi_id: u32,
@@ -8,7 +9,7 @@ pub struct TargetDevice {
     pub version: u32
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Model {
     pub model_id: u32,
@@ -18,29 +19,21 @@ pub struct Model {
     pub params: Vec<ModelParam>
 }
 
-impl PartialEq for Model {
-    fn eq(&self, other: &Self) -> bool {
-        self.slot_id == other.slot_id &&
-            self.model_id == other.model_id &&
-            self.ordinal == other.ordinal
-    }
-}
-
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct ModelParam {
     pub param_id: u32,
     pub value: Value
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Value {
     Int(u32),
     Float(f32)
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct MetaTags {
     pub author: String,
@@ -57,7 +50,7 @@ pub struct MetaTags {
     pub comments: String
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum PatchType {
     #[default]
@@ -66,7 +59,7 @@ pub enum PatchType {
     FxSetup
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct L6Patch {
     pub patch_type: PatchType,
@@ -75,7 +68,7 @@ pub struct L6Patch {
     pub meta: MetaTags
 }
 
-#[derive(Debug, Default, PartialEq, Eq)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum BundleType {
     #[default]
@@ -83,10 +76,14 @@ pub enum BundleType {
     Collection
 }
 
-#[derive(Debug, Default)]
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct L6Bundle {
     pub bundle_type: BundleType,
+    /// HEAD version
+    pub version: u32,
+    pub target_device: Option<TargetDevice>,
+    pub meta: Option<MetaTags>,
     pub banks: Vec<Bank>
 }
 
@@ -94,14 +91,61 @@ pub(crate) struct BatchHead {
     pub version: u32
 }
 
-#[derive(Debug, Default)]
+/// A bank of a bundle or a folder of a collection. Folders may hold
+/// other folders and metadata.
+#[derive(Clone, Debug, Default, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Bank {
     pub name: String,
-    pub patches: Vec<L6Patch>
+    pub patches: Vec<L6Patch>,
+    pub folders: Vec<Bank>,
+    pub meta: Option<MetaTags>,
+    /// First word of the bank info (BINF), its meaning is unknown
+    pub info: u32,
+    /// Chunks of the bank not known to the decoder, written back as read
+    #[cfg_attr(feature = "serde", serde(skip))]
+    pub unknown_chunks: Vec<Chunk>
+}
+
+impl Bank {
+    /// Patches of the bank and all nested folders, depth first
+    pub fn all_patches(&self) -> Vec<&L6Patch> {
+        let mut patches = self.patches.iter().collect::<Vec<_>>();
+        for folder in &self.folders {
+            patches.extend(folder.all_patches());
+        }
+        patches
+    }
+
+    /// The bank and all nested folders, depth first, each named by its
+    /// path, e.g. "Artist/Album/Song", and without nested folders
+    pub fn into_flat(self) -> Vec<Bank> {
+        let mut banks = vec![];
+        flatten_into(self, None, &mut banks);
+        banks
+    }
+}
+
+fn flatten_into(bank: Bank, parent: Option<&str>, banks: &mut Vec<Bank>) {
+    let name = match parent {
+        Some(parent) => format!("{}/{}", parent, bank.name),
+        None => bank.name
+    };
+    banks.push(Bank {
+        name: name.clone(),
+        patches: bank.patches,
+        folders: vec![],
+        meta: bank.meta,
+        info: bank.info,
+        unknown_chunks: bank.unknown_chunks
+    });
+    for folder in bank.folders {
+        flatten_into(folder, Some(&name), banks);
+    }
 }
 
 pub(crate) struct BankInfo {
+    pub info: u32,
     pub name: String
 }
 
@@ -124,6 +168,18 @@ impl Default for MetaTags {
     }
 }
 
+impl Default for L6Bundle {
+    fn default() -> Self {
+        L6Bundle {
+            bundle_type: BundleType::Bundle,
+            version: 1,
+            target_device: None,
+            meta: None,
+            banks: vec![]
+        }
+    }
+}
+
 impl Default for TargetDevice {
     fn default() -> Self {
         TargetDevice {
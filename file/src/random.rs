@@ -0,0 +1,122 @@
+//! Random patches for round-trip testing, only available with the
+//! `testing` feature.
+//!
+//! `Rng` is a small deterministic xorshift generator, so a failing test
+//! can be reproduced from its seed. Generated patches only contain what
+//! the L6T format can represent: param ids of 24 bits, dates in whole
+//! seconds and device names of up to 15 characters.
+use crate::model::{L6Patch, MetaTags, Model, ModelParam, PatchType, TargetDevice, Value};
+
+pub struct Rng {
+    state: u64
+}
+
+impl Rng {
+    pub fn new(seed: u64) -> Self {
+        const MIX: u64 = 0x9e3779b97f4a7c15;
+        // xorshift state must not be zero, it would stay zero
+        let state = match seed ^ MIX {
+            0 => MIX,
+            state => state
+        };
+        Rng { state }
+    }
+
+    /// xorshift64*
+    pub fn next_u64(&mut self) -> u64 {
+        self.state ^= self.state >> 12;
+        self.state ^= self.state << 25;
+        self.state ^= self.state >> 27;
+        self.state.wrapping_mul(0x2545f4914f6cdd1d)
+    }
+
+    pub fn next_u32(&mut self) -> u32 {
+        (self.next_u64() >> 32) as u32
+    }
+
+    /// Random number in `0 .. n`, `n` must not be zero
+    pub fn below(&mut self, n: u32) -> u32 {
+        ((self.next_u32() as u64 * n as u64) >> 32) as u32
+    }
+
+    /// Random number in `0.0 .. 1.0`
+    pub fn next_f32(&mut self) -> f32 {
+        (self.next_u32() >> 8) as f32 / (1u32 << 24) as f32
+    }
+
+    pub fn next_bool(&mut self) -> bool {
+        self.next_u32() & 1 == 1
+    }
+
+    pub fn choose<'a, T>(&mut self, items: &'a [T]) -> Option<&'a T> {
+        if items.is_empty() {
+            return None;
+        }
+        items.get(self.below(items.len() as u32) as usize)
+    }
+
+    /// Random string of printable characters, including some non-ASCII ones
+    pub fn string(&mut self, max_len: u32) -> String {
+        const CHARS: &[char] = &[
+            'a', 'b', 'c', 'x', 'y', 'z', 'A', 'Z', '0', '9', ' ', '-', '#', '/', 'é', 'ü', 'ß', '♯'
+        ];
+        let len = self.below(max_len + 1);
+        (0..len).map(|_| *self.choose(CHARS).unwrap()).collect()
+    }
+}
+
+fn random_value(rng: &mut Rng) -> Value {
+    if rng.next_bool() {
+        Value::Int(rng.below(1024))
+    } else {
+        Value::Float(rng.next_f32())
+    }
+}
+
+fn random_model(rng: &mut Rng, ordinal: u8) -> Model {
+    let params = (0..rng.below(12))
+        .map(|_| ModelParam { param_id: rng.next_u32() & 0x00ffffff, value: random_value(rng) })
+        .collect();
+
+    Model {
+        model_id: rng.next_u32(),
+        slot_id: rng.next_u32(),
+        enabled: rng.next_bool(),
+        ordinal,
+        params
+    }
+}
+
+pub fn random_meta(rng: &mut Rng) -> MetaTags {
+    MetaTags {
+        author: rng.string(20),
+        guitarist: rng.string(20),
+        band: rng.string(20),
+        song: rng.string(20),
+        style: rng.string(20),
+        pickup_style: rng.string(20),
+        pickup_position: rng.string(20),
+        date: rng.below(2_000_000_000) as usize * 1000,
+        amp_name: rng.string(20),
+        creator_app: rng.string(20),
+        creator_app_version: rng.string(10),
+        comments: rng.string(100)
+    }
+}
+
+/// Random raw patch for the device, the models and params don't need
+/// to make sense for the device
+pub fn random_patch(rng: &mut Rng, midi_id: u32) -> L6Patch {
+    let target_device = TargetDevice { midi_id, name: rng.string(15), version: rng.next_u32() };
+    let models = (0..rng.below(16))
+        .map(|n| random_model(rng, n as u8))
+        .collect();
+
+    let patch_type = *rng.choose(&[PatchType::Patch, PatchType::AmpSetup, PatchType::FxSetup]).unwrap();
+    L6Patch {
+        patch_type,
+        target_device,
+        models,
+        meta: random_meta(rng)
+    }
+}
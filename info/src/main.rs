@@ -7,24 +7,45 @@ mod pretty_info;
 use std::fmt::Write as FmtWrite;
 use std::fs::File;
 use std::io::{Read, Write};
+use std::path::Path;
 use std::sync::OnceLock;
 use clap::{CommandFactory, FromArgMatches};
 
 use l6t::iff::Chunk;
 use l6t::decoder::{Decoder, DecoderResult};
-use l6t::model::L6Patch;
-use l6t::symbolic::data::{data_model_by_id, data_model_by_num, data_model_info_by_id, data_model_keys};
+use l6t::encoder::Encoder;
+use l6t::model::{L6Patch, MetaTags, PatchType, TargetDevice};
+use l6t::symbolic::data::external::{load_catalog, load_data_model};
+use l6t::symbolic::bundle::bundle_capacity;
+use l6t::symbolic::data::{data_model_by_id, data_model_by_num, data_model_info_by_id, data_model_keys, Capacity};
 use l6t::symbolic::model::DataModel;
-use l6t::symbolic::value::read_values;
+use l6t::symbolic::value::{apply_values, read_values, write_values, Diagnostic, ValueMap};
+use l6t::symbolic::detect::select_data_model;
+use l6t::symbolic::fingerprint::{fingerprint, Fingerprint, FingerprintOptions};
+use l6t::symbolic::chain::{signal_chain, Block};
 use l6t::symbolic::group::group_values;
-use l6t::symbolic::rich::{enrich_values, RichValueGroup};
-use crate::opts::Opts;
+use l6t::symbolic::label::system_language;
+use l6t::symbolic::library::Library;
+use l6t::symbolic::query::Query;
+use l6t::symbolic::report::library_report;
+use l6t::symbolic::lint::lint_data_model;
+use l6t::symbolic::pack::{pack_uses, PackUse};
+use l6t::symbolic::validate::validate_values;
+use l6t::symbolic::schema::json_schema;
+use l6t::symbolic::setup::{apply_setup, extract_setup};
+use l6t::symbolic::rich::{enrich_values, parse_value, RichValueGroup};
+use crate::opts::{Command, DiagnosticKind, Opts, SetupKind};
 use crate::pretty::{Pretty, PrettyPrinter};
 
 pub struct DecodedPatch {
     patch: L6Patch,
     values: Vec<RichValueGroup>,
-    errors: Vec<String>
+    chain: Vec<Block>,
+    packs: Vec<PackUse>,
+    errors: Vec<Diagnostic>,
+    fingerprint: Fingerprint,
+    /// Device program of a patch of a bundle, e.g. "12C"
+    program: Option<String>
 }
 
 pub struct DecodedBank {
@@ -34,6 +55,10 @@ pub struct DecodedBank {
 
 pub struct DecodedBundle {
     is_bundle: bool,
+    version: u32,
+    target_device: Option<TargetDevice>,
+    meta: Option<MetaTags>,
+    capacity: Option<Capacity>,
     banks: Vec<DecodedBank>
 }
 
@@ -45,7 +70,11 @@ fn get_help_text() -> &'static String {
         writeln!(s, "Supported data models (-m):").unwrap();
         for (n, id) in data_model_keys().iter().enumerate() {
             let info = data_model_info_by_id(*id).unwrap();
-            writeln!(s, "    [{}] {:#010x} {}", n, id, info.name).unwrap();
+            match info.alias_of {
+                None => writeln!(s, "    [{}] {:#010x} {}", n, id, info.name).unwrap(),
+                Some(alias_of) => writeln!(s, "    [{}] {:#010x} {} (same as {:#010x})",
+                                           n, id, info.name, alias_of).unwrap()
+            }
         }
 
         s
@@ -53,29 +82,45 @@ fn get_help_text() -> &'static String {
 }
 
 fn get_model(patch: &L6Patch, model_num: &Option<usize>) -> &'static DataModel {
-    model_num
-        .and_then(|num|
-            data_model_by_num(num)
-                .or_else(|| panic!("Data model not found by number: {}", num))
-        )
-        .or_else(|| {
-            let id = patch.target_device.midi_id;
-            data_model_by_id(id)
-                .or_else(|| panic!("Data model not found by device id: {:#x}", id))
-        })
-        .unwrap()
+    if let Some(num) = model_num {
+        return data_model_by_num(*num)
+            .unwrap_or_else(|| panic!("Data model not found by number: {}", num));
+    }
+
+    let id = patch.target_device.midi_id;
+    let Some(candidate) = select_data_model(patch) else {
+        panic!("Data model not found by device id: {:#x}", id)
+    };
+    if !data_model_by_id(id).is_some_and(|m| std::ptr::eq(m, candidate.model)) {
+        eprintln!("Device id {:#x} doesn't fit the patch, detected {} ({:#x}) with confidence {:.0} %",
+                  id, candidate.name, candidate.id, candidate.confidence * 100.0);
+    }
+
+    candidate.model
+}
 
+fn diagnostic_kind(diagnostic: &Diagnostic) -> DiagnosticKind {
+    match diagnostic {
+        Diagnostic::MissingParam { .. } => DiagnosticKind::MissingParam,
+        Diagnostic::InvalidParamType { .. } => DiagnosticKind::InvalidParamType,
+        Diagnostic::UnprocessedParam { .. } => DiagnosticKind::UnprocessedParam,
+        Diagnostic::UnprocessedModel { .. } => DiagnosticKind::UnprocessedModel
+    }
 }
 
-fn decoder_result_to_bundle(dr: DecoderResult, model_num: Option<usize>) -> DecodedBundle {
+fn decoder_result_to_bundle(dr: DecoderResult, model_num: Option<usize>, hide: &[DiagnosticKind]) -> DecodedBundle {
     let patch_to_decoded = |patch: L6Patch| {
         let model = get_model(&patch, &model_num);
 
-        let (values, errors) = read_values(&patch, model);
+        let (values, mut errors) = read_values(&patch, model);
+        errors.retain(|d| !hide.contains(&diagnostic_kind(d)));
         let values = enrich_values(values, &model.info_map);
         let values = group_values(&patch, &values, model);
+        let chain = signal_chain(&patch, model);
+        let packs = pack_uses(&patch, model);
+        let fingerprint = fingerprint(&patch, &FingerprintOptions::default());
 
-        DecodedPatch { patch, values, errors }
+        DecodedPatch { patch, values, chain, packs, errors, fingerprint, program: None }
     };
 
 
@@ -85,25 +130,83 @@ fn decoder_result_to_bundle(dr: DecoderResult, model_num: Option<usize>) -> Deco
             let bank = DecodedBank { name: "".into(), patches: vec![ p ] };
             DecodedBundle {
                 is_bundle: false,
+                version: 0,
+                target_device: None,
+                meta: None,
+                capacity: None,
                 banks: vec![ bank ]
             }
         }
         DecoderResult::Bundle(b) => {
+            let capacity = bundle_capacity(&b);
             let mut banks = vec![];
-            for b in b.banks {
+            // nested folders of collections are listed as banks named by path
+            for (n, b) in b.banks.into_iter().flat_map(|b| b.into_flat()).enumerate() {
                 let name = b.name;
                 let patches = b.patches.into_iter()
                     .map(patch_to_decoded)
+                    .enumerate()
+                    .map(|(i, p)| DecodedPatch { program: capacity.and_then(|c| c.program_name(n, i)), ..p })
                     .collect();
                 banks.push(DecodedBank { name, patches });
             }
-            DecodedBundle { is_bundle: true, banks }
+            DecodedBundle {
+                is_bundle: true,
+                version: b.version,
+                target_device: b.target_device,
+                meta: b.meta,
+                capacity,
+                banks
+            }
         }
     }
 
 }
 
 
+fn scan_library(dir: &Path) -> Library {
+    let library = Library::scan(dir)
+        .unwrap_or_else(|e| panic!("Failed to scan {:?}: {}", dir, e));
+    for (path, e) in &library.errors {
+        eprintln!("{}: {}", path.display(), e);
+    }
+    library
+}
+
+fn search(dir: &Path, query: &str) {
+    let query = Query::parse(query)
+        .unwrap_or_else(|e| panic!("Invalid query: {}", e));
+    let library = scan_library(dir);
+
+    let found = library.search(&query);
+    for entry in &found {
+        let mut location = entry.path.display().to_string();
+        if let Some(bank) = &entry.bank {
+            write!(location, " [{}]", bank).unwrap();
+        }
+        if let Some(program) = &entry.program {
+            write!(location, " ({})", program).unwrap();
+        }
+        println!("{}: {} - {}", location, entry.name(), entry.device.unwrap_or("unknown device"));
+    }
+    eprintln!("{} of {} patches match", found.len(), library.entries.len());
+}
+
+fn report(dir: &Path, query: &str, csv: bool) {
+    let query = (!query.is_empty()).then(|| Query::parse(query)
+        .unwrap_or_else(|e| panic!("Invalid query: {}", e)));
+    let library = scan_library(dir);
+    let report = match &query {
+        Some(query) => library_report(library.search(query)),
+        None => library_report(&library.entries)
+    };
+    if csv {
+        print!("{}", report.to_csv());
+    } else {
+        print!("{}", report);
+    }
+}
+
 fn main() -> Result<(), clap::error::Error> {
     let matches = Opts::command()
         .after_help(get_help_text())
@@ -111,9 +214,48 @@ fn main() -> Result<(), clap::error::Error> {
         .get_matches();
     let opts = Opts::from_arg_matches(&matches)?;
     let mut pp = PrettyPrinter::with_simple(opts.dump_simple);
+    pp.lang = opts.lang.clone().unwrap_or_else(system_language);
+
+    for path in &opts.catalogs {
+        load_catalog(path)
+            .unwrap_or_else(|e| panic!("Failed to load catalog {:?}: {}", path, e))
+            .register();
+    }
+
+    for path in &opts.data_models {
+        let model = load_data_model(path)
+            .unwrap_or_else(|e| panic!("Failed to load data model {:?}: {}", path, e))
+            .register();
+        for issue in lint_data_model(model) {
+            eprintln!("{}: {}", path.display(), issue);
+        }
+    }
+
+    match &opts.command {
+        Some(Command::Search { dir, query }) => {
+            search(dir, &query.join(" "));
+            return Ok(());
+        }
+        Some(Command::Report { dir, csv, query }) => {
+            report(dir, &query.join(" "), *csv);
+            return Ok(());
+        }
+        None => {}
+    }
+
+    if opts.schema {
+        let num = opts.model.unwrap();
+        let (id, info) = data_model_keys().get(num)
+            .and_then(|id| Some((*id, data_model_info_by_id(*id)?)))
+            .unwrap_or_else(|| panic!("Data model not found by number: {}", num));
+        let mut schema = json_schema(info.model, info.name);
+        schema["x-device-id"] = id.into();
+        println!("{}", serde_json::to_string_pretty(&schema).unwrap());
+        return Ok(());
+    }
 
     let mut v: Vec<u8> = Vec::new();
-    File::open(opts.file).unwrap()
+    File::open(opts.file.unwrap()).unwrap()
         .read_to_end(&mut v).unwrap();
 
     if opts.dump_iff {
@@ -130,29 +272,92 @@ fn main() -> Result<(), clap::error::Error> {
         pp.println(patch).unwrap();
     }
 
-    let bundle = decoder_result_to_bundle(decoded, opts.model);
+    let mut bundle = decoder_result_to_bundle(decoded, opts.model, &opts.hide);
+    if let Some(program) = &opts.program {
+        let (bank, patch) = bundle.capacity
+            .and_then(|c| c.parse_program(program))
+            .unwrap_or_else(|| panic!("No program {} on the device of the bundle", program));
+        if bundle.banks.get(bank).map_or(true, |b| patch >= b.patches.len()) {
+            panic!("Program {} of the bundle is empty", program);
+        }
+        let bank = bundle.banks.swap_remove(bank);
+        let patches = bank.patches.into_iter().skip(patch).take(1).collect();
+        bundle.banks = vec![ DecodedBank { name: bank.name, patches } ];
+    }
     pp.println(&bundle).unwrap();
 
-/*
     if let Some(write_filename) = opts.write {
-        let patch = if opts.encode {
-            let p = write_values(values, model);
-            // for now write_values doesn't do anything target_devices and meta
-            // fields, so take them from the original patch
-            L6Patch {
-                target_device: patch.target_device,
-                models: p.models,
-                meta: patch.meta,
+        if bundle.is_bundle {
+            panic!("Writing bundles is not supported");
+        }
+        let decoded = bundle.banks.into_iter().next()
+            .and_then(|b| b.patches.into_iter().next())
+            .unwrap();
+
+        let mut patch = if opts.encode {
+            let model = get_model(&decoded.patch, &opts.model);
+            let values = decoded.values.iter()
+                .flat_map(|g| g.values.iter())
+                .map(|(name, value)| (name.clone(), value.get_simple().clone()))
+                .collect::<ValueMap>();
+            let violations = validate_values(&values, model);
+            if !violations.is_empty() {
+                let msg = violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("\n");
+                panic!("Refusing to encode invalid values:\n{}", msg);
             }
+            let mut p = write_values(&values, model, decoded.patch.meta)
+                .unwrap_or_else(|e| panic!("Failed to encode values: {}", e));
+            // write_values doesn't know the patch name, take it from the original patch
+            p.target_device.name = decoded.patch.target_device.name;
+            p.patch_type = decoded.patch.patch_type;
+            p
         } else {
-            patch
+            decoded.patch
         };
 
+        if !opts.set.is_empty() {
+            let model = get_model(&patch, &opts.model);
+            let values = opts.set.iter().map(|s| {
+                let (name, value) = s.split_once('=')
+                    .unwrap_or_else(|| panic!("Expected NAME=VALUE, got \"{}\"", s));
+                let name = name.trim();
+                let value = parse_value(name, value, model)
+                    .unwrap_or_else(|e| panic!("Failed to parse value for \"{}\": {}", name, e));
+                (name.to_string(), value)
+            }).collect::<ValueMap>();
+            let violations = validate_values(&values, model);
+            if !violations.is_empty() {
+                let msg = violations.iter().map(|v| v.to_string()).collect::<Vec<_>>().join("\n");
+                panic!("Refusing to set invalid values:\n{}", msg);
+            }
+            apply_values(&mut patch, &values, model)
+                .unwrap_or_else(|e| panic!("Failed to set values: {}", e));
+        }
+
+        if let Some(path) = &opts.apply_setup {
+            let data = std::fs::read(path)
+                .unwrap_or_else(|e| panic!("Failed to read {:?}: {}", path, e));
+            let DecoderResult::Patch(setup) = Decoder::read(&data).unwrap() else {
+                panic!("Expected an amp setup or fx setup in {:?}", path);
+            };
+            apply_setup(&mut patch, &setup)
+                .unwrap_or_else(|e| panic!("Failed to apply setup: {}", e));
+        }
+
+        if let Some(kind) = opts.extract_setup {
+            let patch_type = match kind {
+                SetupKind::Amp => PatchType::AmpSetup,
+                SetupKind::Fx => PatchType::FxSetup
+            };
+            patch = extract_setup(&patch, patch_type)
+                .unwrap_or_else(|e| panic!("Failed to extract setup: {}", e));
+        }
+
         let vec = Encoder::write(&patch).unwrap();
         File::create(write_filename).unwrap()
-            .write(&vec).unwrap();
+            .write_all(&vec).unwrap();
     }
-*/
+
     Ok(())
 }
 
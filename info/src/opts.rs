@@ -1,9 +1,12 @@
 use std::path::PathBuf;
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
 #[derive(Parser)]
-#[command(version, about)]
+#[command(version, about, args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 pub struct Opts {
+    #[command(subcommand)]
+    pub command: Option<Command>,
+
     #[clap(short = 'i', long)]
     /// Dump IFF chunks loaded from the file
     pub dump_iff: bool,
@@ -24,11 +27,90 @@ pub struct Opts {
     /// File to write L&T patch to
     pub write: Option<PathBuf>,
 
+    #[clap(long = "set", value_name = "NAME=VALUE")]
+    /// Set a value before writing, e.g. "delay_time=350 ms" or "amp_select=Plexi 45"
+    pub set: Vec<String>,
+
+    #[clap(long, value_name = "FILE")]
+    /// Apply an amp setup or fx setup file onto the patch before writing
+    pub apply_setup: Option<PathBuf>,
+
+    #[clap(long, value_enum, value_name = "SETUP")]
+    /// Write only the amp setup or fx setup of the patch
+    pub extract_setup: Option<SetupKind>,
+
+    #[clap(short = 'd', long = "data-model", value_name = "FILE")]
+    /// Load a data model definition (TOML or JSON) overriding or extending
+    /// the built-in data models, may be repeated
+    pub data_models: Vec<PathBuf>,
+
+    #[clap(short = 'c', long = "catalog", value_name = "FILE")]
+    /// Load a label catalog (TOML or JSON) for a language, may be repeated
+    pub catalogs: Vec<PathBuf>,
+
+    #[clap(short = 'l', long)]
+    /// Language of param labels, defaults to the language set in LANG
+    pub lang: Option<String>,
+
     #[clap(short = 'm', long)]
     /// Data model number
     pub model: Option<usize>,
 
+    #[clap(long, value_enum, value_name = "KIND")]
+    /// Don't print diagnostics of this kind, may be repeated
+    pub hide: Vec<DiagnosticKind>,
+
+    #[clap(long, value_name = "PROGRAM")]
+    /// Only print the patch of a bundle at this device program, e.g. "12C"
+    pub program: Option<String>,
 
+    #[clap(long, requires = "model")]
+    /// Print JSON Schema of the values of the data model selected with -m
+    pub schema: bool,
+
+    #[clap(required_unless_present = "schema")]
     /// File to print out the info for
-    pub file: PathBuf
-}
\ No newline at end of file
+    pub file: Option<PathBuf>
+}
+
+#[derive(Subcommand)]
+pub enum Command {
+    /// Search the patches of the files in a directory and its subdirectories,
+    /// e.g. `amp_select = "Plexi 45" AND delay_enable` or `band ~ Floyd`
+    Search {
+        /// Directory to search
+        dir: PathBuf,
+
+        #[clap(required = true)]
+        /// Query, fields are param names, meta tags (author, band, ...), name,
+        /// device, file, bank and program; operators are =, !=, ~, <, <=, >, >=
+        query: Vec<String>
+    },
+    /// Report model usage, value distributions, authors, styles and params
+    /// never changed of the patches of the files in a directory
+    Report {
+        /// Directory to report on
+        dir: PathBuf,
+
+        #[clap(long)]
+        /// Print CSV instead of text
+        csv: bool,
+
+        /// Only report on the patches matching this query, see `search`
+        query: Vec<String>
+    }
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum DiagnosticKind {
+    MissingParam,
+    InvalidParamType,
+    UnprocessedParam,
+    UnprocessedModel
+}
+
+#[derive(ValueEnum, Clone, Copy, PartialEq)]
+pub enum SetupKind {
+    Amp,
+    Fx
+}
@@ -9,6 +9,8 @@ pub struct PrettyPrinter {
     pub full: bool,
     ///
     pub with_simple: bool,
+    /// Language of param labels
+    pub lang: String,
 }
 
 impl PrettyPrinter {
@@ -16,7 +18,7 @@ impl PrettyPrinter {
         Self::with_simple(false)
     }
     pub fn with_simple(with_simple: bool) -> Self {
-        PrettyPrinter { indent: 0, step: 2, buffer: "".into(), full: true, with_simple }
+        PrettyPrinter { indent: 0, step: 2, buffer: "".into(), full: true, with_simple, lang: "en".into() }
     }
 
     fn indent(&mut self) {
@@ -1,5 +1,7 @@
 use std::sync::OnceLock;
+use l6t::symbolic::chain::Block;
 use l6t::symbolic::group::ValueGroup;
+use l6t::symbolic::label::label;
 use l6t::symbolic::rich::RichValueGroup;
 use crate::{DecodedBundle, DecodedPatch};
 use crate::pretty::*;
@@ -24,13 +26,13 @@ impl Pretty for Vec<RichValueGroup> {
     fn fmt(&self, pp: &mut PrettyPrinter) -> fmt::Result {
         let sep = sep();
         for group in self {
-            writeln!(pp, "{}\n{}", group.name, sep)?;
+            writeln!(pp, "{}\n{}", label(&group.name, &pp.lang), sep)?;
 
             for (name, value) in &group.values {
-                writeln!(pp, "{:30} : {}", name, value)?;
+                writeln!(pp, "{:30} : {}", label(name, &pp.lang), value)?;
                 if pp.with_simple {
                     let simple = value.get_simple();
-                    writeln!(pp, "{:30} : {:5} : {}", "", simple.get_type(), simple)?;
+                    writeln!(pp, "{:30} : {:5} : {}", name, simple.get_type(), simple)?;
                 }
 /*
                 if value.is_simple() {
@@ -65,12 +67,42 @@ impl Pretty for Vec<ValueGroup> {
     }
 }
 
+impl Pretty for Vec<Block> {
+    fn fmt(&self, pp: &mut PrettyPrinter) -> fmt::Result {
+        writeln!(pp, "Signal chain\n{}", sep())?;
+        for block in self {
+            let name = label(&block.name, &pp.lang);
+            let name = match &block.model_name {
+                Some(model_name) => format!("{} ({})", name, model_name),
+                None => name
+            };
+            let enabled = if block.enabled { "on" } else { "off" };
+            let section = format!("{:?}", block.section);
+            writeln!(pp, "{:30} : {:7} : {}", name, section, enabled)?;
+        }
+        writeln!(pp)?;
+
+        Ok(())
+    }
+}
+
 impl Pretty for DecodedPatch {
     fn fmt(&self, pp: &mut PrettyPrinter) -> fmt::Result {
         Pretty::fmt_full(&self.patch, pp, false)?;
+        writeln!(pp, "Fingerprint: {}", self.fingerprint)?;
         writeln!(pp)?;
 
         Pretty::fmt(&self.values, pp)?;
+        Pretty::fmt(&self.chain, pp)?;
+
+        if !self.packs.is_empty() {
+            writeln!(pp, "MODEL PACKS\n{}", sep())?;
+            for u in self.packs.iter() {
+                let value = u.label.clone().unwrap_or_else(|| format!("{:#x}", u.key));
+                writeln!(pp, "{:30} : {} ({})", label(&u.name, &pp.lang), value, u.pack)?;
+            }
+            writeln!(pp)?;
+        }
 
         if !self.errors.is_empty() {
             writeln!(pp, "ERRORS\n{}", sep())?;
@@ -91,6 +123,15 @@ impl Pretty for DecodedBundle {
             return Ok(())
         }
 
+        writeln!(pp, "Bundle version: {}", self.version)?;
+        if let Some(meta) = &self.meta {
+            Pretty::fmt(meta, pp)?;
+        }
+        if let Some(target_device) = &self.target_device {
+            Pretty::fmt(target_device, pp)?;
+        }
+        writeln!(pp)?;
+
         let sep = hsep();
         for (n, bank) in self.banks.iter().enumerate() {
             writeln!(pp, "[{}/{}] Bank: {}\n{}\n", n+1, self.banks.len(), bank.name, sep)?;
@@ -100,6 +141,9 @@ impl Pretty for DecodedBundle {
                 // This will prefix the L6Patch pretty output to form a seamless line:
                 // [xx/xx] Patch type: ...
                 write!(pp, "[{}/{}] ", n+1, bank.patches.len())?;
+                if let Some(program) = &patch.program {
+                    write!(pp, "({}) ", program)?;
+                }
                 Pretty::fmt(patch, pp)?;
             }
         }
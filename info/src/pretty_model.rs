@@ -28,7 +28,14 @@ impl Pretty for model::L6Bundle {
             BundleType::Collection => "collection"
         };
         writeln!(pp, "Bundle type: {}", bundle_type)?;
+        writeln!(pp, "Bundle version: {}", self.version)?;
         writeln!(pp)?;
+        if let Some(meta) = &self.meta {
+            Pretty::fmt(meta, pp)?;
+        }
+        if let Some(target_device) = &self.target_device {
+            Pretty::fmt(target_device, pp)?;
+        }
         Pretty::fmt(&self.banks, pp)?;
         Ok(())
     }
@@ -52,9 +59,15 @@ impl Pretty for model::Bank {
         writeln!(pp, "Bank '{}':", self.name)?;
         pp.indent += 1;
 
+        if let Some(meta) = &self.meta {
+            Pretty::fmt(meta, pp)?;
+        }
         for v in self.patches.iter() {
             Pretty::fmt(v, pp)?;
         }
+        for v in self.folders.iter() {
+            Pretty::fmt(v, pp)?;
+        }
         pp.indent -= 1;
         Ok(())
     }
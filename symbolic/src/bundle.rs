@@ -0,0 +1,249 @@
+//! Editing bundles.
+//!
+//! Patches are addressed by bank index and patch index within the bank.
+//! Bundles hold patches of a single device, the device of the first
+//! patch. Bundles (but not collections) of devices with a known
+//! `Capacity` can't hold more banks or more patches per bank than the
+//! device has.
+use thiserror::Error;
+use file::model::{Bank, BundleType, L6Bundle, L6Patch};
+use crate::data::{data_model_info_by_id, Capacity};
+
+#[cfg(test)]
+mod tests;
+
+/// Bank names are stored as 32 UTF-16 characters
+pub const BANK_NAME_LEN: usize = 32;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BundleError {
+    #[error("No bank {0}")]
+    NoSuchBank(usize),
+    #[error("No patch {patch} in bank {bank}")]
+    NoSuchPatch { bank: usize, patch: usize },
+    #[error("Bank {bank} is full, the device holds {capacity} patches per bank")]
+    BankFull { bank: usize, capacity: usize },
+    #[error("Bundle is full, the device holds {capacity} banks")]
+    BundleFull { capacity: usize },
+    #[error("Patch is for device {found:#x}, the bundle is for device {expected:#x}")]
+    DeviceMismatch { expected: u32, found: u32 },
+    #[error("Bank name longer than {BANK_NAME_LEN} characters")]
+    NameTooLong
+}
+
+/// Device id of the patches of the bundle, including the patches of
+/// nested folders, `None` for an empty bundle
+pub fn bundle_device(bundle: &L6Bundle) -> Option<u32> {
+    bundle.banks.iter()
+        .flat_map(|b| b.all_patches())
+        .map(|p| p.target_device.midi_id)
+        .next()
+}
+
+/// Capacity limiting the bundle, `None` for collections and bundles of
+/// devices with an unknown capacity
+pub fn bundle_capacity(bundle: &L6Bundle) -> Option<Capacity> {
+    device_capacity(&bundle.bundle_type, bundle_device(bundle))
+}
+
+/// Program name of the patch position on the device of the bundle, e.g.
+/// "12C", see `Capacity::program_name`
+pub fn program_name(bundle: &L6Bundle, bank: usize, patch: usize) -> Option<String> {
+    bundle_capacity(bundle)?.program_name(bank, patch)
+}
+
+/// Patch position of the program name on the device of the bundle, e.g.
+/// "03A", see `Capacity::parse_program`
+pub fn program_position(bundle: &L6Bundle, name: &str) -> Option<(usize, usize)> {
+    bundle_capacity(bundle)?.parse_program(name)
+}
+
+/// Device of the patches, checking they are all for the `expected`
+/// device, if any
+fn check_devices<'a>(expected: Option<u32>, patches: impl IntoIterator<Item = &'a L6Patch>) -> Result<Option<u32>, BundleError> {
+    let mut device = expected;
+    for patch in patches {
+        let found = patch.target_device.midi_id;
+        match device {
+            Some(expected) if expected != found =>
+                return Err(BundleError::DeviceMismatch { expected, found }),
+            _ => device = Some(found)
+        }
+    }
+    Ok(device)
+}
+
+fn device_capacity(bundle_type: &BundleType, device: Option<u32>) -> Option<Capacity> {
+    if *bundle_type != BundleType::Bundle {
+        return None;
+    }
+    device.and_then(data_model_info_by_id)
+        .and_then(|info| info.capacity)
+}
+
+fn check_bank(bundle: &L6Bundle, bank: usize) -> Result<(), BundleError> {
+    if bank >= bundle.banks.len() {
+        return Err(BundleError::NoSuchBank(bank));
+    }
+    Ok(())
+}
+
+fn check_patch(bundle: &L6Bundle, bank: usize, patch: usize) -> Result<(), BundleError> {
+    check_bank(bundle, bank)?;
+    if patch >= bundle.banks[bank].patches.len() {
+        return Err(BundleError::NoSuchPatch { bank, patch });
+    }
+    Ok(())
+}
+
+fn check_name(name: &str) -> Result<(), BundleError> {
+    if name.encode_utf16().count() > BANK_NAME_LEN {
+        return Err(BundleError::NameTooLong);
+    }
+    Ok(())
+}
+
+fn check_banks(banks: usize, capacity: Option<Capacity>) -> Result<(), BundleError> {
+    match capacity {
+        Some(capacity) if banks > capacity.banks =>
+            Err(BundleError::BundleFull { capacity: capacity.banks }),
+        _ => Ok(())
+    }
+}
+
+/// Insert a patch at the index of the bank, `index` may be the number
+/// of patches of the bank to append the patch
+pub fn insert_patch(bundle: &mut L6Bundle, bank: usize, index: usize, patch: L6Patch) -> Result<(), BundleError> {
+    check_bank(bundle, bank)?;
+    let device = check_devices(bundle_device(bundle), [&patch])?;
+    let len = bundle.banks[bank].patches.len();
+    if index > len {
+        return Err(BundleError::NoSuchPatch { bank, patch: index });
+    }
+    if let Some(capacity) = device_capacity(&bundle.bundle_type, device) {
+        if len >= capacity.patches {
+            return Err(BundleError::BankFull { bank, capacity: capacity.patches });
+        }
+    }
+    bundle.banks[bank].patches.insert(index, patch);
+
+    Ok(())
+}
+
+pub fn remove_patch(bundle: &mut L6Bundle, bank: usize, index: usize) -> Result<L6Patch, BundleError> {
+    check_patch(bundle, bank, index)?;
+    Ok(bundle.banks[bank].patches.remove(index))
+}
+
+/// Move a patch to another position, in the same or another bank. The
+/// index in the target bank is the index after the patch is removed.
+pub fn move_patch(bundle: &mut L6Bundle, from: (usize, usize), to: (usize, usize)) -> Result<(), BundleError> {
+    check_patch(bundle, from.0, from.1)?;
+    check_bank(bundle, to.0)?;
+    let len = bundle.banks[to.0].patches.len() - if from.0 == to.0 { 1 } else { 0 };
+    if to.1 > len {
+        return Err(BundleError::NoSuchPatch { bank: to.0, patch: to.1 });
+    }
+    if let Some(capacity) = bundle_capacity(bundle) {
+        if from.0 != to.0 && len >= capacity.patches {
+            return Err(BundleError::BankFull { bank: to.0, capacity: capacity.patches });
+        }
+    }
+    let patch = bundle.banks[from.0].patches.remove(from.1);
+    bundle.banks[to.0].patches.insert(to.1, patch);
+
+    Ok(())
+}
+
+/// Swap two patches, in the same or different banks
+pub fn swap_patches(bundle: &mut L6Bundle, a: (usize, usize), b: (usize, usize)) -> Result<(), BundleError> {
+    check_patch(bundle, a.0, a.1)?;
+    check_patch(bundle, b.0, b.1)?;
+    let patch_a = std::mem::take(&mut bundle.banks[a.0].patches[a.1]);
+    let patch_b = std::mem::replace(&mut bundle.banks[b.0].patches[b.1], patch_a);
+    bundle.banks[a.0].patches[a.1] = patch_b;
+
+    Ok(())
+}
+
+/// Insert an empty bank at the index, `index` may be the number of banks
+/// to append the bank
+pub fn insert_bank(bundle: &mut L6Bundle, index: usize, name: &str) -> Result<(), BundleError> {
+    check_name(name)?;
+    if index > bundle.banks.len() {
+        return Err(BundleError::NoSuchBank(index));
+    }
+    check_banks(bundle.banks.len() + 1, bundle_capacity(bundle))?;
+    bundle.banks.insert(index, Bank { name: name.to_string(), ..Default::default() });
+
+    Ok(())
+}
+
+pub fn remove_bank(bundle: &mut L6Bundle, index: usize) -> Result<Bank, BundleError> {
+    check_bank(bundle, index)?;
+    Ok(bundle.banks.remove(index))
+}
+
+pub fn rename_bank(bundle: &mut L6Bundle, index: usize, name: &str) -> Result<(), BundleError> {
+    check_bank(bundle, index)?;
+    check_name(name)?;
+    bundle.banks[index].name = name.to_string();
+
+    Ok(())
+}
+
+/// All patches of the bundle, bank by bank, the patches of nested folders
+/// after the patches of their parent
+pub fn split_bundle(bundle: L6Bundle) -> Vec<L6Patch> {
+    bundle.banks.into_iter()
+        .flat_map(|b| b.into_flat())
+        .flat_map(|b| b.patches)
+        .collect()
+}
+
+/// Add the patches after the last patch of the bundle, filling up the
+/// last bank and then new banks named "Bank N". Nothing is added if not
+/// all the patches fit.
+pub fn append_patches(bundle: &mut L6Bundle, patches: Vec<L6Patch>) -> Result<(), BundleError> {
+    let device = check_devices(bundle_device(bundle), &patches)?;
+    let per_bank = device_capacity(&bundle.bundle_type, device).map(|c| c.patches);
+
+    let free = bundle.banks.last()
+        .map_or(0, |b| per_bank.map_or(usize::MAX, |n| n.saturating_sub(b.patches.len())));
+    let in_last = free.min(patches.len());
+    let new_banks = match per_bank {
+        Some(n) => (patches.len() - in_last + n - 1) / n,
+        None => usize::from(in_last < patches.len())
+    };
+    check_banks(bundle.banks.len() + new_banks, device_capacity(&bundle.bundle_type, device))?;
+
+    let mut patches = patches.into_iter();
+    if let Some(bank) = bundle.banks.last_mut() {
+        bank.patches.extend(patches.by_ref().take(in_last));
+    }
+    for _ in 0 .. new_banks {
+        let name = format!("Bank {}", bundle.banks.len() + 1);
+        let patches = patches.by_ref().take(per_bank.unwrap_or(usize::MAX)).collect();
+        bundle.banks.push(Bank { name, patches, ..Default::default() });
+    }
+
+    Ok(())
+}
+
+/// Merge bundles into one, keeping their banks. The bundle type, version,
+/// target device and metadata are the ones of the first bundle.
+pub fn merge_bundles(bundles: Vec<L6Bundle>) -> Result<L6Bundle, BundleError> {
+    let mut bundles = bundles.into_iter();
+    let mut merged = bundles.next().unwrap_or_default();
+    merged.banks.extend(bundles.flat_map(|b| b.banks));
+
+    let device = check_devices(None, merged.banks.iter().flat_map(|b| b.all_patches()))?;
+    if let Some(capacity) = device_capacity(&merged.bundle_type, device) {
+        if let Some(bank) = merged.banks.iter().position(|b| b.patches.len() > capacity.patches) {
+            return Err(BundleError::BankFull { bank, capacity: capacity.patches });
+        }
+        check_banks(merged.banks.len(), Some(capacity))?;
+    }
+
+    Ok(merged)
+}
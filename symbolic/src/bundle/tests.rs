@@ -0,0 +1,58 @@
+use file::model::{Bank, BundleType, L6Bundle};
+use crate::bundle::{append_patches, bundle_device, insert_bank, insert_patch, merge_bundles, move_patch, remove_patch, rename_bank, split_bundle, swap_patches, BundleError};
+use crate::value::new_patch;
+
+#[test]
+fn test_bundle_edit() {
+    let named = |name: &str| {
+        let mut patch = new_patch(0x030002).unwrap();
+        patch.target_device.name = name.to_string();
+        patch
+    };
+    let names = |bundle: &L6Bundle| bundle.banks.iter()
+        .map(|b| b.patches.iter().map(|p| p.target_device.name.as_str()).collect::<Vec<_>>().join(""))
+        .collect::<Vec<_>>();
+
+    // PODxt: 16 banks of 4 patches
+    let mut bundle = L6Bundle::default();
+    append_patches(&mut bundle, "abcdef".chars().map(|c| named(&c.to_string())).collect()).unwrap();
+    assert_eq!(names(&bundle), vec!["abcd", "ef"]);
+    assert_eq!(bundle.banks[1].name, "Bank 2");
+
+    assert_eq!(insert_patch(&mut bundle, 0, 0, named("x")), Err(BundleError::BankFull { bank: 0, capacity: 4 }));
+    insert_patch(&mut bundle, 1, 0, named("x")).unwrap();
+    move_patch(&mut bundle, (0, 0), (0, 3)).unwrap();
+    swap_patches(&mut bundle, (0, 0), (1, 2)).unwrap();
+    assert_eq!(names(&bundle), vec!["fcda", "xeb"]);
+    assert_eq!(move_patch(&mut bundle, (1, 0), (0, 0)), Err(BundleError::BankFull { bank: 0, capacity: 4 }));
+    assert_eq!(remove_patch(&mut bundle, 1, 0).unwrap().target_device.name, "x");
+    assert!(matches!(remove_patch(&mut bundle, 1, 2), Err(BundleError::NoSuchPatch { bank: 1, patch: 2 })));
+
+    rename_bank(&mut bundle, 1, "Gig").unwrap();
+    assert_eq!(rename_bank(&mut bundle, 1, &"x".repeat(33)), Err(BundleError::NameTooLong));
+    let pod2 = new_patch(0x000300).unwrap();
+    assert!(matches!(insert_patch(&mut bundle, 1, 0, pod2), Err(BundleError::DeviceMismatch { .. })));
+
+    let mut full = L6Bundle::default();
+    append_patches(&mut full, (0 .. 64).map(|_| named("z")).collect()).unwrap();
+    assert_eq!(full.banks.len(), 16);
+    assert_eq!(insert_bank(&mut full, 0, "More"), Err(BundleError::BundleFull { capacity: 16 }));
+    assert_eq!(append_patches(&mut full, vec![named("z")]), Err(BundleError::BundleFull { capacity: 16 }));
+    assert_eq!(full.banks.len(), 16);
+    assert!(merge_bundles(vec![full, L6Bundle { banks: vec![], ..Default::default() }]).is_ok());
+
+    // patches of nested folders of collections
+    let nested = Bank { name: "Album".into(), patches: vec![named("n")], ..Default::default() };
+    let artist = Bank { name: "Artist".into(), folders: vec![nested], ..Default::default() };
+    let nested = L6Bundle { bundle_type: BundleType::Collection, banks: vec![artist], ..Default::default() };
+    assert_eq!(bundle_device(&nested), Some(0x030002));
+    assert!(matches!(merge_bundles(vec![nested.clone(), L6Bundle { banks: vec![Bank { patches: vec![new_patch(0x000300).unwrap()], ..Default::default() }], ..Default::default() }]),
+                     Err(BundleError::DeviceMismatch { .. })));
+    assert_eq!(split_bundle(nested).len(), 1);
+
+    let collection = L6Bundle { bundle_type: BundleType::Collection, ..Default::default() };
+    let mut merged = merge_bundles(vec![collection, bundle]).unwrap();
+    append_patches(&mut merged, (0 .. 64).map(|_| named("z")).collect()).unwrap();
+    assert_eq!(names(&merged)[1].len(), 66);
+    assert_eq!(split_bundle(merged).len(), 70);
+}
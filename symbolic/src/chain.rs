@@ -0,0 +1,85 @@
+//! Signal chain of a patch.
+//!
+//! The devices don't store the order of the effects explicitly. Instead,
+//! every effect block is a model in a slot and the slot ids are ordered
+//! along the signal path: 0x2xxxx slots are before the amp, 0x3xxxx are
+//! the amp section (amp, cab, room, EQ) and 0x5xxxx are after the amp.
+//! Effects that can be placed pre or post the amp (modulation, delay,
+//! reverb, volume pedal) use a different slot id for each position,
+//! e.g. 0x20004 vs 0x50003 for modulation, see `mod_position`.
+use file::model::L6Patch;
+use crate::model::{DataModel, Param};
+use crate::value::{model_matches_slot, Value};
+
+/// Section of the signal chain a block is in
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Section {
+    PreAmp,
+    Amp,
+    PostAmp
+}
+
+impl Section {
+    pub(crate) fn from_slot_id(slot_id: u32) -> Option<Section> {
+        match slot_id >> 16 {
+            2 => Some(Section::PreAmp),
+            3 => Some(Section::Amp),
+            5 => Some(Section::PostAmp),
+            _ => None
+        }
+    }
+}
+
+/// An effect block of the signal chain
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Block {
+    /// Name of the data model group, e.g. "Stomp" or "Delay"
+    pub name: String,
+    pub section: Section,
+    pub slot_id: u32,
+    pub model_id: u32,
+    /// Display name of the model, if the slot selects one from a lookup
+    pub model_name: Option<String>,
+    pub enabled: bool
+}
+
+fn model_name(model: &DataModel, params: &[Param], model_id: u32) -> Option<String> {
+    let name = params.iter().find_map(|p| match p {
+        Param::SlotModel { name } => Some(name),
+        _ => None
+    })?;
+    model.choices(name)?.into_iter()
+        .find(|c| c.value == Value::Int(model_id))
+        .map(|c| c.label)
+}
+
+/// Ordered signal chain of the patch, from input to output. Every group
+/// of the data model that matches a model of the patch is a block, the
+/// first matching slot of the group determines its position. Patch-wide
+/// settings (slot 0x10000) are not part of the chain.
+pub fn signal_chain(patch: &L6Patch, model: &DataModel) -> Vec<Block> {
+    let mut blocks = vec![];
+    for group in &model.groups {
+        let found = group.slots.iter().find_map(|slot| {
+            patch.models.iter()
+                .find(|m| model_matches_slot(m, slot))
+                .map(|m| (slot, m))
+        });
+        let Some((slot, patch_model)) = found else { continue };
+        let Some(section) = Section::from_slot_id(patch_model.slot_id) else { continue };
+
+        blocks.push(Block {
+            name: group.name.clone(),
+            section,
+            slot_id: patch_model.slot_id,
+            model_id: patch_model.model_id,
+            model_name: model_name(model, &slot.params, patch_model.model_id),
+            enabled: patch_model.enabled
+        });
+    }
+
+    blocks.sort_by_key(|b| b.slot_id);
+    blocks
+}
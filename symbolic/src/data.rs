@@ -1,12 +1,16 @@
 mod models;
 pub mod shorthand;
+#[cfg(feature = "external")]
+pub mod external;
 
 mod podxt;
 mod pod2;
 mod pocketpod;
 mod floorpod;
 mod basspodxt;
+mod labels;
 #[cfg(test)]
 mod tests;
 
 pub use models::*;
+pub(crate) use labels::english_catalog;
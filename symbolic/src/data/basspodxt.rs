@@ -31,16 +31,19 @@ use maplit::{convert_args, hashmap};
 use crate::data::shorthand::*;
 use crate::data::models::filter_params_by_prefix;
 use crate::data::podxt;
-use crate::data::podxt::{_1457_value, _1m335_value, footswitch_mode_select, heads_value, mod_slot, mod_slot5, note_select, pedal_assign_select, podxt_data_model, stomp_slot, wave_value};
-use crate::model::{DataModel, Group, Param, Slot};
-
-/// Filter amp and cab list to contain the "BX-..." items only,
-/// stripping the "BX-" prefix along the way.
-fn bx_only(map: &HashMap<u32, String>) -> HashMap<u32, String> {
+use crate::data::podxt::{_1457_value, _1m335_value, footswitch_mode_select, heads_value, mod_slot, mod_slot5, note_select, pedal_assign_select, podxt_data_model, stomp_slot, synced_delay_time, wave_value};
+use crate::model::{DataModel, Group, ModelPack, PackMap, Param, Slot};
+use crate::value::{Value, ValueMap};
+
+/// Filter PODxt amp or cab list to contain the Bass Expansion items
+/// only, which are stock models of Bass PODxt, stripping the "BX-"
+/// prefix along the way.
+fn bx_only(name: &str, map: &HashMap<u32, String>) -> HashMap<u32, String> {
+    let prefix = ModelPack::BassExpansion.prefix();
     map.iter()
         .flat_map(|(key, value)| {
-            if !value.starts_with("BX-") { return None }
-            let value = value.strip_prefix("BX-").unwrap();
+            if podxt_data_model().pack(name, *key) != Some(ModelPack::BassExpansion) { return None }
+            let value = value.strip_prefix(prefix).unwrap_or(value);
 
             Some((*key, value.to_string()))
         })
@@ -53,7 +56,7 @@ fn amp_select() -> &'static HashMap<u32, String> {
         let mut map = convert_args!(keys=identity::<u32>, values=String::from, hashmap!(
             196608 => "Bypass",
         ));
-        map.extend(bx_only(podxt::amp_select()).into_iter());
+        map.extend(bx_only("amp_select", podxt::amp_select()).into_iter());
         map
     })
 }
@@ -64,7 +67,7 @@ fn cab_select() -> &'static HashMap<u32, String> {
         let mut map = convert_args!(keys=identity::<u32>, values=String::from, hashmap!(
             16973824 => "No Cab",
         ));
-        map.extend(bx_only(podxt::cab_select()).into_iter());
+        map.extend(bx_only("cab_select", podxt::cab_select()).into_iter());
         map
     })
 }
@@ -367,6 +370,7 @@ fn delay_slot(select: u32, model: u32, params: &[Option<&str>; 4]) -> Slot {
             param(3, 0x010001), // param 4
             float(0x010002, "delay_reverb_di_xover"),
             int(0x200000, "delay_note_select"),
+            synced_delay_time(),
         ]
     }
 }
@@ -690,6 +694,7 @@ fn basspodxt_data_model_all() -> &'static DataModel {
             "mod_select" => lookup(mod_select()),
             "mod_speed" => hz().from_to(0.0, 0.1, 1.0, 15.0),
             "mod_note_select" => lookup(note_select()),
+            "mod_effective_speed" => hz(),
             "mod_depth" => percent(),
             "mod_bass" => percent(),
             "mod_treble" => percent(),
@@ -706,6 +711,7 @@ fn basspodxt_data_model_all() -> &'static DataModel {
             "delay_reverb_select" => lookup(delay_reverb_select()),
             "delay_time" => millis().from_to(0.0, 20.0, 1.0, 2000.0),
             "delay_note_select" => lookup(note_select()),
+            "delay_effective_time" => millis(),
             "delay_feedback" => percent(),
             "delay_bass" => percent(),
             "delay_treble" => percent(),
@@ -757,7 +763,14 @@ fn basspodxt_data_model_all() -> &'static DataModel {
         DataModel {
             floats_as_ints: false,
             groups,
-            info_map
+            info_map,
+            packs: PackMap::new(),
+            defaults: ValueMap::from([
+                ("amp_select".into(), Value::Int(196621)), // Flip Top
+                ("amp_enable".into(), Value::Bool(true)),
+                ("cab_select".into(), Value::Int(16973827)), // 1x15 Flip Top
+                ("tempo".into(), Value::Float(120.0)),
+            ])
         }
     })
 }
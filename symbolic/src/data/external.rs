@@ -0,0 +1,486 @@
+//! Data models defined in external TOML or JSON files.
+//!
+//! A definition either describes a complete data model or, with `base`
+//! set to the device id of a known data model, extends that model:
+//! groups with a known name get their slots appended, `slot_params`
+//! add or replace params of existing slots, `info` entries replace the
+//! ones of the base model and `extend_lookups` add entries to lookups
+//! of the base model, `packs` assign lookup entries to model packs and
+//! `defaults` set default values of params in the format they are
+//! displayed in. A minimal TOML extension looks like this:
+//!
+//! ```toml
+//! id = 0x030002
+//! base = 0x030002
+//!
+//! [[slot_params]]
+//! fixed_slot = 0x30000
+//! params = [ { type = "float", id = 0x100008, name = "amp_presence" } ]
+//!
+//! [info.amp_presence]
+//! format = "percent"
+//!
+//! [extend_lookups.amp_select]
+//! 0x020099 = "Secret Amp"
+//! ```
+//!
+//! Loaded data models are put to use with `ExternalDataModel::register`.
+//! Ids may be given as numbers or as (hex) strings, since JSON has no
+//! hex number literals. Lookup tables are leaked to get the `'static`
+//! lifetime built-in data models have, so data models are expected to
+//! be loaded once at start-up.
+//!
+//! Label catalogs (see `crate::label`) for other languages are loaded
+//! from the same formats with `load_catalog`:
+//!
+//! ```toml
+//! lang = "de"
+//!
+//! [labels.amp_bypass_volume]
+//! label = "Lautstärke Amp-Bypass"
+//! description = "Ausgangslautstärke bei umgangenem Amp-Modell"
+//! ```
+use std::collections::HashMap;
+use std::path::Path;
+use serde::{Deserialize, Deserializer};
+use serde::de::Error as _;
+use thiserror::Error;
+use crate::data::{data_model_by_id, data_model_info_by_id, replace_data_model};
+use crate::data::shorthand::*;
+use crate::label::{register_catalog, Catalog};
+use crate::model::{DataModel, Group, ModelPack, Param, Slot};
+use crate::rich::{parse_value, FormattingType, ParseError, ValueInfo};
+
+#[derive(Error, Debug)]
+pub enum LoadError {
+    #[error("I/O error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("TOML error: {0}")]
+    Toml(#[from] toml::de::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("Unknown file extension: {0:?}")]
+    UnknownFormat(String),
+    #[error("Base data model not found by device id: {0:#x}")]
+    UnknownBase(u32),
+    #[error("Invalid default value of {0:?}: {1}")]
+    InvalidDefault(String, ParseError),
+    #[error("Lookup {0:?} not defined")]
+    UnknownLookup(String),
+    #[error("Param {0:?} has no lookup to extend")]
+    NotALookup(String),
+    #[error("Invalid lookup key {0:?}")]
+    InvalidKey(String),
+    #[error("Data model without a base must define a device id")]
+    MissingId,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct DataModelDef {
+    /// Device id to register the data model with, defaults to `base`
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub id: Option<u32>,
+    pub name: Option<String>,
+    /// Device id of a known data model to extend
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub base: Option<u32>,
+    pub floats_as_ints: Option<bool>,
+    #[serde(default)]
+    pub lookups: HashMap<String, LookupDef>,
+    #[serde(default)]
+    pub groups: Vec<GroupDef>,
+    #[serde(default)]
+    pub slot_params: Vec<SlotParamsDef>,
+    #[serde(default)]
+    pub info: HashMap<String, InfoDef>,
+    #[serde(default)]
+    pub extend_lookups: HashMap<String, LookupDef>,
+    /// Model packs of lookup entries, e.g. `[packs.amp_select]` with
+    /// `0x020099 = "MetalShop"`
+    #[serde(default)]
+    pub packs: HashMap<String, HashMap<String, ModelPack>>,
+    /// Default values of params as displayed, e.g. `amp_select = "Plexi 45"`
+    #[serde(default)]
+    pub defaults: HashMap<String, String>,
+}
+
+/// Int lookup as a table of "id" = "name" or a float lookup as
+/// an array of [start, "name"] pairs
+#[derive(Deserialize, Debug)]
+#[serde(untagged)]
+pub enum LookupDef {
+    Int(HashMap<String, String>),
+    Float(Vec<(u32, String)>),
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct GroupDef {
+    pub name: String,
+    pub slots: Vec<SlotDef>
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SlotDef {
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub fixed_slot: Option<u32>,
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub fixed_model: Option<u32>,
+    pub fixed_enable: Option<bool>,
+    pub params: Vec<ParamDef>
+}
+
+/// Params to add to the existing slots matching `fixed_slot` and
+/// `fixed_model`. A param with the same id as an existing one
+/// replaces it.
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct SlotParamsDef {
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub fixed_slot: Option<u32>,
+    #[serde(default, deserialize_with = "de_opt_id")]
+    pub fixed_model: Option<u32>,
+    pub params: Vec<ParamDef>
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(tag = "type", rename_all = "snake_case", deny_unknown_fields)]
+pub enum ParamDef {
+    Slot { name: String },
+    SlotEnable { name: String },
+    Int { #[serde(deserialize_with = "de_id")] id: u32, name: String },
+    Float { #[serde(deserialize_with = "de_id")] id: u32, name: String },
+    Bool { #[serde(deserialize_with = "de_id")] id: u32, name: String },
+    FixedInt {
+        name: String,
+        #[serde(deserialize_with = "de_id")]
+        value: u32,
+        #[serde(default, deserialize_with = "de_opt_id")]
+        slot_id: Option<u32>
+    },
+    Ignore {
+        #[serde(deserialize_with = "de_id")]
+        id: u32,
+        #[serde(default)]
+        float: bool
+    },
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(rename_all = "snake_case")]
+pub enum FormatDef {
+    Simple,
+    Lookup,
+    FloatLookup,
+    Percent,
+    Millis,
+    Hertz,
+    Decibel,
+    Bpm,
+}
+
+#[derive(Deserialize, Debug)]
+#[serde(deny_unknown_fields)]
+pub struct InfoDef {
+    pub format: FormatDef,
+    /// Decimals for "millis" and "decibel" formats
+    #[serde(default)]
+    pub decimals: usize,
+    /// Lookup name for "lookup" and "float_lookup" formats
+    pub lookup: Option<String>,
+    pub range: Option<(f32, f32)>,
+    /// Linear conversion f(x) = k(a + x) + b as [k, a, b]
+    pub linear: Option<(f32, f32, f32)>,
+    /// Linear conversion through two points as [x1, y1, x2, y2]
+    pub from_to: Option<(f32, f32, f32, f32)>,
+    pub points: Option<Vec<(f32, f32)>>,
+    pub points_l6e: Option<Vec<(u32, f32)>>,
+}
+
+/// A data model loaded from an external definition
+pub struct ExternalDataModel {
+    pub id: u32,
+    pub name: Option<String>,
+    pub model: DataModel
+}
+
+impl ExternalDataModel {
+    /// Register the data model, overriding the data model registered
+    /// with the same device id, if any. Without a name, the name of the
+    /// overridden data model is kept.
+    pub fn register(self) -> &'static DataModel {
+        let name = self.name
+            .or_else(|| data_model_info_by_id(self.id).map(|i| i.name.to_string()))
+            .unwrap_or_else(|| format!("Device {:#08x}", self.id));
+        replace_data_model(self.id, &name, self.model);
+
+        data_model_by_id(self.id).unwrap()
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum IdDef {
+    Num(u32),
+    Str(String)
+}
+
+fn parse_id(s: &str) -> Option<u32> {
+    let s = s.trim();
+    match s.strip_prefix("0x").or_else(|| s.strip_prefix("0X")) {
+        Some(hex) => u32::from_str_radix(&hex.replace('_', ""), 16).ok(),
+        None => s.replace('_', "").parse().ok()
+    }
+}
+
+fn de_id<'de, D: Deserializer<'de>>(d: D) -> Result<u32, D::Error> {
+    match IdDef::deserialize(d)? {
+        IdDef::Num(v) => Ok(v),
+        IdDef::Str(s) => parse_id(&s).ok_or_else(|| D::Error::custom(format!("invalid id {:?}", s)))
+    }
+}
+
+fn de_opt_id<'de, D: Deserializer<'de>>(d: D) -> Result<Option<u32>, D::Error> {
+    de_id(d).map(Some)
+}
+
+fn leak_lookup(def: &LookupDef) -> Result<FormattingType, LoadError> {
+    let formatting_type = match def {
+        LookupDef::Int(map) => {
+            let map = int_lookup(map)?;
+            FormattingType::IntLookup(Box::leak(Box::new(map)))
+        }
+        LookupDef::Float(vec) => {
+            let mut vec = vec.clone();
+            vec.sort_by_key(|(k, _)| *k);
+            FormattingType::FloatLookup(Box::leak(Box::new(vec)))
+        }
+    };
+    Ok(formatting_type)
+}
+
+fn int_lookup(map: &HashMap<String, String>) -> Result<HashMap<u32, String>, LoadError> {
+    map.iter()
+        .map(|(k, v)| {
+            let k = parse_id(k).ok_or_else(|| LoadError::InvalidKey(k.clone()))?;
+            Ok((k, v.clone()))
+        })
+        .collect()
+}
+
+impl ParamDef {
+    fn to_param(&self) -> Param {
+        match self {
+            ParamDef::Slot { name } => slot(name),
+            ParamDef::SlotEnable { name } => slot_enable(name),
+            ParamDef::Int { id, name } => int(*id, name),
+            ParamDef::Float { id, name } => float(*id, name),
+            ParamDef::Bool { id, name } => bool(*id, name),
+            ParamDef::FixedInt { name, value, slot_id: None } => fixed_int(name, *value),
+            ParamDef::FixedInt { name, value, slot_id: Some(slot_id) } =>
+                fixed_int_for_slot_id(name, *value, *slot_id),
+            ParamDef::Ignore { id, float: false } => ignore(*id),
+            ParamDef::Ignore { id, float: true } => ignore_f(*id),
+        }
+    }
+}
+
+impl SlotDef {
+    fn to_slot(&self) -> Slot {
+        Slot {
+            fixed_slot: self.fixed_slot,
+            fixed_model: self.fixed_model,
+            fixed_enable: self.fixed_enable,
+            params: self.params.iter().map(|p| p.to_param()).collect()
+        }
+    }
+}
+
+impl InfoDef {
+    fn to_value_info(&self, lookups: &HashMap<String, FormattingType>) -> Result<ValueInfo, LoadError> {
+        let lookup = || {
+            let name = self.lookup.clone().unwrap_or_default();
+            lookups.get(&name).cloned().ok_or(LoadError::UnknownLookup(name))
+        };
+        let mut builder = match self.format {
+            FormatDef::Simple => ValueInfoBuilder::new(),
+            FormatDef::Lookup | FormatDef::FloatLookup => ValueInfoBuilder::new().formatting_type(lookup()?),
+            FormatDef::Percent => percent(),
+            FormatDef::Millis => ValueInfoBuilder::new().formatting_type(FormattingType::Millis(self.decimals)),
+            FormatDef::Hertz => hz(),
+            FormatDef::Decibel => ValueInfoBuilder::new().formatting_type(FormattingType::Decibel(self.decimals)),
+            FormatDef::Bpm => bpm(),
+        };
+        if let Some((k, a, b)) = self.linear {
+            builder = builder.convert(k, a, b);
+        }
+        if let Some((x1, y1, x2, y2)) = self.from_to {
+            builder = builder.from_to(x1, y1, x2, y2);
+        }
+        if let Some(points) = &self.points {
+            builder = builder.points(points);
+        }
+        if let Some(points) = &self.points_l6e {
+            builder = builder.points_l6e(points);
+        }
+        if let Some((min, max)) = self.range {
+            builder = builder.range(min, max);
+        }
+
+        Ok(builder.into())
+    }
+}
+
+impl DataModelDef {
+    pub fn from_toml(s: &str) -> Result<DataModelDef, LoadError> {
+        Ok(toml::from_str(s)?)
+    }
+
+    pub fn from_json(s: &str) -> Result<DataModelDef, LoadError> {
+        Ok(serde_json::from_str(s)?)
+    }
+
+    /// Build the data model, extending the base data model if one is given
+    pub fn build(&self) -> Result<ExternalDataModel, LoadError> {
+        let base = match self.base {
+            None => None,
+            Some(id) => Some(data_model_by_id(id).ok_or(LoadError::UnknownBase(id))?)
+        };
+        let id = self.id.or(self.base).ok_or(LoadError::MissingId)?;
+
+        let mut groups = base.map(|m| m.groups.clone()).unwrap_or_default();
+        let mut info_map = base.map(|m| m.info_map.clone()).unwrap_or_default();
+        let mut packs = base.map(|m| m.packs.clone()).unwrap_or_default();
+        let defaults = base.map(|m| m.defaults.clone()).unwrap_or_default();
+        let floats_as_ints = self.floats_as_ints
+            .or(base.map(|m| m.floats_as_ints))
+            .unwrap_or(false);
+
+        for group in &self.groups {
+            let slots = group.slots.iter().map(|s| s.to_slot());
+            match groups.iter_mut().find(|g| g.name == group.name) {
+                Some(g) => g.slots.extend(slots),
+                None => groups.push(Group { name: group.name.clone(), slots: slots.collect() })
+            }
+        }
+
+        for def in &self.slot_params {
+            let slots = groups.iter_mut()
+                .flat_map(|g| g.slots.iter_mut())
+                .filter(|s| def.fixed_slot.map_or(true, |id| s.fixed_slot == Some(id)))
+                .filter(|s| def.fixed_model.map_or(true, |id| s.fixed_model == Some(id)));
+            for slot in slots {
+                for param in def.params.iter().map(|p| p.to_param()) {
+                    let existing = param.get_id()
+                        .and_then(|id| slot.params.iter().position(|p| p.get_id() == Some(id)));
+                    match existing {
+                        Some(pos) => slot.params[pos] = param,
+                        None => slot.params.push(param)
+                    }
+                }
+            }
+        }
+
+        for (name, def) in &self.extend_lookups {
+            let info = info_map.get_mut(name).ok_or_else(|| LoadError::NotALookup(name.clone()))?;
+            info.formatting_type = match (&info.formatting_type, def) {
+                (FormattingType::IntLookup(map), LookupDef::Int(extra)) => {
+                    let mut map = (*map).clone();
+                    map.extend(int_lookup(extra)?);
+                    FormattingType::IntLookup(Box::leak(Box::new(map)))
+                }
+                (FormattingType::FloatLookup(vec), LookupDef::Float(extra)) => {
+                    let mut vec = (*vec).clone();
+                    vec.retain(|(k, _)| !extra.iter().any(|(e, _)| e == k));
+                    vec.extend(extra.iter().cloned());
+                    vec.sort_by_key(|(k, _)| *k);
+                    FormattingType::FloatLookup(Box::leak(Box::new(vec)))
+                }
+                _ => return Err(LoadError::NotALookup(name.clone()))
+            };
+        }
+
+        let lookups = self.lookups.iter()
+            .map(|(name, def)| Ok((name.clone(), leak_lookup(def)?)))
+            .collect::<Result<HashMap<_, _>, LoadError>>()?;
+        for (name, def) in &self.info {
+            info_map.insert(name.clone(), def.to_value_info(&lookups)?);
+        }
+
+        for (name, def) in &self.packs {
+            let entries = packs.entry(name.clone()).or_default();
+            for (key, pack) in def {
+                let key = parse_id(key).ok_or_else(|| LoadError::InvalidKey(key.clone()))?;
+                entries.insert(key, *pack);
+            }
+        }
+
+        let mut model = DataModel { floats_as_ints, groups, info_map, packs, defaults };
+        for (name, input) in &self.defaults {
+            let value = parse_value(name, input, &model)
+                .map_err(|e| LoadError::InvalidDefault(name.clone(), e))?;
+            model.defaults.insert(name.clone(), value);
+        }
+
+        Ok(ExternalDataModel { id, name: self.name.clone(), model })
+    }
+}
+
+/// Load a data model definition from a ".toml" or ".json" file
+pub fn load_data_model(path: &Path) -> Result<ExternalDataModel, LoadError> {
+    let s = std::fs::read_to_string(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let def = match ext.to_ascii_lowercase().as_str() {
+        "toml" => DataModelDef::from_toml(&s)?,
+        "json" => DataModelDef::from_json(&s)?,
+        _ => return Err(LoadError::UnknownFormat(ext.to_string()))
+    };
+
+    def.build()
+}
+
+#[derive(Deserialize)]
+struct LabelDef {
+    label: String,
+    #[serde(default)]
+    description: Option<String>,
+}
+
+#[derive(Deserialize)]
+struct CatalogDef {
+    lang: String,
+    #[serde(default)]
+    labels: HashMap<String, LabelDef>,
+}
+
+/// Label catalog loaded from a file, not registered yet
+pub struct ExternalCatalog {
+    pub lang: String,
+    pub catalog: Catalog
+}
+
+impl ExternalCatalog {
+    /// Add the labels to the catalog of the language
+    pub fn register(self) {
+        register_catalog(&self.lang, self.catalog)
+    }
+}
+
+/// Load a label catalog from a ".toml" or ".json" file
+pub fn load_catalog(path: &Path) -> Result<ExternalCatalog, LoadError> {
+    let s = std::fs::read_to_string(path)?;
+    let ext = path.extension().and_then(|e| e.to_str()).unwrap_or_default();
+    let def: CatalogDef = match ext.to_ascii_lowercase().as_str() {
+        "toml" => toml::from_str(&s)?,
+        "json" => serde_json::from_str(&s)?,
+        _ => return Err(LoadError::UnknownFormat(ext.to_string()))
+    };
+
+    let mut catalog = Catalog::new();
+    for (key, def) in &def.labels {
+        catalog.insert(key, &def.label, def.description.as_deref());
+    }
+    Ok(ExternalCatalog { lang: def.lang, catalog })
+}
@@ -4,7 +4,8 @@ use std::sync::OnceLock;
 use maplit::{convert_args, hashmap};
 use crate::data::pod2::{reverb_type, rotary_speed};
 use crate::data::shorthand::*;
-use crate::model::{DataModel, Group, Slot};
+use crate::model::{DataModel, Group, PackMap, Slot};
+use crate::value::ValueMap;
 
 /* NOTES: - Vyzex does not save "amp mode enable" to a L6T.
           - Vyzex does not save "wah enable" to a L6T.
@@ -116,6 +117,7 @@ fn effect_select() -> &'static HashMap<u32, String> {
             5 => "rotary",
             6 => "phaser",
             7 => "u-vibe",
+            8 => "obi-wah", // the slot with fixed_int("effect_select", 8) and model 0x20a001f
             9 => "tron-up",
             10 => "octave fuzz",
             11 => "sub octave",
@@ -672,7 +674,9 @@ pub fn floorpod_data_model() -> &'static DataModel {
         DataModel {
             floats_as_ints: false,
             groups,
-            info_map
+            info_map,
+            packs: PackMap::new(),
+            defaults: ValueMap::new()
         }
     })
 }
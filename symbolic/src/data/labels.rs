@@ -0,0 +1,94 @@
+use crate::label::Catalog;
+
+/// English labels of params whose keys don't read well on their own.
+/// Other params get labels made from their keys.
+pub(crate) fn english_catalog() -> Catalog {
+    let labels: &[(&str, &str, Option<&str>)] = &[
+        // Misc
+        ("amp_bypass_volume", "Amp bypass volume", Some("Output volume when the amp model is bypassed")),
+        ("tempo", "Tempo", Some("Tempo synced delay and modulation times follow")),
+        ("di_model", "DI model", Some("Amount of amp model in the direct (DI) output")),
+        ("di_delay", "DI delay", Some("Delay of the direct (DI) output to line it up with the mic")),
+        ("di_xover", "DI crossover", Some("Frequency below which the signal bypasses the amp model")),
+        ("mod_di_xover", "Modulation crossover", Some("Frequency below which the signal bypasses the modulation")),
+        ("delay_reverb_di_xover", "Delay/reverb crossover", Some("Frequency below which the signal bypasses delay and reverb")),
+        ("pedal_assign", "Pedal assign", Some("What the expression pedals control")),
+        ("tweak_param_select", "Tweak param", Some("Param the TWEAK knob or pedal controls")),
+        ("loop_enable", "Effects loop", Some("Effects loop of PODxt Pro / Bass PODxt Pro")),
+        ("footswitch_mode", "Footswitch mode", Some("Footswitch mode of PODxt Live / Bass PODxt Live")),
+        ("variax_300_500_700.model_select", "Variax model", Some("Variax 300/500/700 guitar model")),
+        ("variax_300_500_700.tone", "Variax tone", Some("Variax 300/500/700 tone knob")),
+        ("variax_acoustic.model_select", "Variax Acoustic model", Some("Variax Acoustic guitar model")),
+        ("variax_acoustic.mic_pos", "Variax Acoustic mic position", None),
+        ("variax_acoustic.comp", "Variax Acoustic compression", None),
+        ("variax_bass.model_select", "Variax Bass model", Some("Variax Bass model")),
+        ("variax_bass.blend", "Variax Bass pickup blend", None),
+        ("variax_bass.bass", "Variax Bass bass", None),
+        ("variax_bass.treble", "Variax Bass treble", None),
+
+        // Amp & cab
+        ("amp_select", "Amp model", None),
+        ("amp_enable", "Amp", Some("Amp model on or bypassed")),
+        ("chan_volume", "Channel volume", Some("Volume of the amp model")),
+        ("cab_select", "Cab model", None),
+        ("mic_select", "Mic", Some("Microphone model in front of the cab")),
+        ("room", "Room", Some("Amount of room ambience mixed to the mic")),
+        ("air", "Air", Some("Amount of room ambience mixed to the cab sound")),
+        ("drive2", "Drive 2", None),
+
+        // Gate & compressor
+        ("gate_enable", "Noise gate", None),
+        ("gate_threshold", "Gate threshold", Some("Level below which the gate closes")),
+        ("gate_decay", "Gate decay", Some("How fast the gate closes")),
+        ("comp_enable", "Compressor", None),
+        ("comp_threshold", "Compressor threshold", Some("Level above which the signal is compressed")),
+        ("comp_gain", "Compressor gain", Some("Make-up gain after compression")),
+
+        // Positions
+        ("mod_position", "Modulation position", Some("Modulation before (pre) or after (post) the amp")),
+        ("delay_position", "Delay position", Some("Delay before (pre) or after (post) the amp")),
+        ("reverb_position", "Reverb position", Some("Reverb before (pre) or after (post) the amp")),
+        ("vol_pedal_position", "Volume pedal position", Some("Volume pedal before (pre) or after (post) the amp")),
+        ("eq_position", "EQ position", Some("EQ before (pre) or after (post) the amp")),
+        ("wah_position", "Wah position", Some("Position of the wah pedal")),
+
+        // Effects
+        ("stomp_select", "Stomp model", None),
+        ("stomp_enable", "Stomp", None),
+        ("mod_select", "Modulation model", None),
+        ("mod_enable", "Modulation", None),
+        ("mod_speed", "Modulation speed", None),
+        ("mod_note_select", "Modulation note", Some("Note value the modulation speed is synced to the tempo with")),
+        ("mod_effective_speed", "Effective modulation speed", Some("Modulation speed with tempo sync applied")),
+        ("delay_select", "Delay model", None),
+        ("delay_enable", "Delay", None),
+        ("delay_note_select", "Delay note", Some("Note value the delay time is synced to the tempo with")),
+        ("delay_effective_time", "Effective delay time", Some("Delay time with tempo sync applied")),
+        ("reverb_select", "Reverb model", None),
+        ("reverb_enable", "Reverb", None),
+        ("wah_select", "Wah model", None),
+        ("wah_enable", "Wah", None),
+        ("vol_min", "Volume pedal minimum", Some("Volume with the pedal all the way back")),
+
+        // EQ
+        ("eq_enable", "EQ", None),
+        ("eq_1_freq", "EQ band 1 frequency", None),
+        ("eq_1_gain", "EQ band 1 gain", None),
+        ("eq_2_freq", "EQ band 2 frequency", None),
+        ("eq_2_gain", "EQ band 2 gain", None),
+        ("eq_3_freq", "EQ band 3 frequency", None),
+        ("eq_3_gain", "EQ band 3 gain", None),
+        ("eq_4_freq", "EQ band 4 frequency", None),
+        ("eq_4_gain", "EQ band 4 gain", None),
+        ("eq_5_freq", "EQ band 5 frequency", None),
+        ("eq_5_gain", "EQ band 5 gain", None),
+        ("eq_6_freq", "EQ band 6 frequency", None),
+        ("eq_6_gain", "EQ band 6 gain", None),
+    ];
+
+    let mut catalog = Catalog::new();
+    for (key, label, description) in labels {
+        catalog.insert(key, label, *description);
+    }
+    catalog
+}
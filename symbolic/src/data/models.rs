@@ -1,5 +1,6 @@
 use std::collections::HashMap;
-use std::sync::OnceLock;
+use std::sync::{OnceLock, RwLock};
+use thiserror::Error;
 use crate::data::basspodxt::*;
 use crate::data::floorpod::*;
 use crate::data::pocketpod::*;
@@ -9,84 +10,218 @@ use crate::model::{DataModel, Group, Param, Slot};
 
 pub struct DataModelInfo {
     pub name: &'static str,
-    pub model: &'static DataModel
-}
-
-pub(crate) fn data_models() -> &'static HashMap<u32, DataModelInfo> {
-    static MODELS: OnceLock<HashMap<u32, DataModelInfo>> = OnceLock::new();
-    MODELS.get_or_init(||
-        HashMap::from([
-            // Line6 Edit sometimes creates L6T files with this identified,
-            // assume it is the same as POD 2.0, although it is probably not...
-            (0x000200, DataModelInfo {
-                name: "Flextone II",
-                model: pod2_data_model(),
-            }),
-            (0x000300, DataModelInfo {
-                name: "POD 2.0 / POD Pro",
-                model: pod2_data_model(),
-            }),
-            (0x030002, DataModelInfo {
-                name: "PODxt",
-                model: podxt_data_model(),
-            }),
-            (0x030005, DataModelInfo {
-                name: "PODxt Pro",
-                model: podxt_pro_data_model(),
-            }),
-            (0x03000a, DataModelInfo {
-                name: "PODxt Live",
-                model: podxt_live_data_model()
-            }),
-            (0x030006, DataModelInfo {
-                name: "Bass PODxt",
-                model: basspodxt_data_model(),
-            }),
-            (0x030007, DataModelInfo {
-                name: "Bass PODxt Pro",
-                model: basspodxt_pro_data_model(),
-            }),
-            (0x03000b, DataModelInfo {
-                name: "Bass PODxt Live",
-                model: basspodxt_live_data_model(),
-            }),
-            (0x000500, DataModelInfo {
-                name: "Floor POD Plus",
-                model: floorpod_data_model(),
-            }),
-            (0x000600, DataModelInfo {
-                name: "Pocket POD",
-                model: pocketpod_data_model(),
-            }),
-        ])
-    )
-}
-
-pub fn data_model_keys() -> &'static Vec<u32> {
-    static KEYS: OnceLock<Vec<u32>> = OnceLock::new();
-    KEYS.get_or_init(|| {
-        let mut keys = data_models().keys().cloned().collect::<Vec<_>>();
-        keys.sort();
-
-        keys
-    })
+    pub model: &'static DataModel,
+    /// Device id of the data model this one is an alias of
+    pub alias_of: Option<u32>,
+    /// User program memory of the device, `None` if not known
+    pub capacity: Option<Capacity>
+}
+
+/// User program memory of a device: a number of banks, each holding
+/// the same number of patches.
+///
+/// Programs are named by the bank number, counting from 1, and the patch
+/// letter, counting from A, e.g. "5B" on POD 2.0. The bank number is
+/// zero-padded to the width of the last bank, e.g. "03A" on PODxt.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Capacity {
+    pub banks: usize,
+    pub patches: usize
+}
+
+impl Capacity {
+    pub fn total(&self) -> usize {
+        self.banks * self.patches
+    }
+
+    fn contains(&self, bank: usize, patch: usize) -> bool {
+        bank < self.banks && patch < self.patches && patch < 26
+    }
+
+    /// Program name of the patch position, e.g. "12C" for bank 11,
+    /// patch 2, `None` if the device has no such program
+    pub fn program_name(&self, bank: usize, patch: usize) -> Option<String> {
+        if !self.contains(bank, patch) {
+            return None;
+        }
+        let width = self.banks.to_string().len();
+        let letter = (b'A' + patch as u8) as char;
+        Some(format!("{:0width$}{}", bank + 1, letter, width = width))
+    }
+
+    /// Patch position of the program name, e.g. "12C", "3a" or "03 A"
+    pub fn parse_program(&self, name: &str) -> Option<(usize, usize)> {
+        let name = name.trim();
+        let letter = name.chars().last()?.to_ascii_uppercase();
+        if !letter.is_ascii_uppercase() {
+            return None;
+        }
+        let bank = name[.. name.len() - 1].trim_end().parse::<usize>().ok()?.checked_sub(1)?;
+        let patch = (letter as u8 - b'A') as usize;
+        self.contains(bank, patch).then_some((bank, patch))
+    }
+
+    /// Program number of the patch position, counting from 0
+    pub fn program_number(&self, bank: usize, patch: usize) -> Option<usize> {
+        self.contains(bank, patch).then_some(bank * self.patches + patch)
+    }
+
+    /// Patch position of the program number, counting from 0
+    pub fn program_position(&self, number: usize) -> Option<(usize, usize)> {
+        (number < self.total()).then_some((number / self.patches, number % self.patches))
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum RegistryError {
+    #[error("Data model already registered for device id {0:#x}")]
+    AlreadyRegistered(u32),
+    #[error("No data model registered for device id {0:#x}")]
+    NotRegistered(u32),
+}
+
+type Registry = HashMap<u32, &'static DataModelInfo>;
+
+fn builtin_data_models() -> Registry {
+    let model = |name, model, banks, patches| -> &'static DataModelInfo {
+        let capacity = Some(Capacity { banks, patches });
+        Box::leak(Box::new(DataModelInfo { name, model, alias_of: None, capacity }))
+    };
+    let alias = |name, alias_of, model| -> &'static DataModelInfo {
+        Box::leak(Box::new(DataModelInfo { name, model, alias_of: Some(alias_of), capacity: None }))
+    };
+
+    HashMap::from([
+        // Line6 Edit sometimes creates L6T files with this identified,
+        // assume it is the same as POD 2.0, although it is probably not...
+        (0x000200, alias("Flextone II", 0x000300, pod2_data_model())),
+        (0x000300, model("POD 2.0 / POD Pro", pod2_data_model(), 9, 4)),
+        (0x030002, model("PODxt", podxt_data_model(), 16, 4)),
+        (0x030005, model("PODxt Pro", podxt_pro_data_model(), 16, 4)),
+        (0x03000a, model("PODxt Live", podxt_live_data_model(), 16, 4)),
+        (0x030006, model("Bass PODxt", basspodxt_data_model(), 16, 4)),
+        (0x030007, model("Bass PODxt Pro", basspodxt_pro_data_model(), 16, 4)),
+        (0x03000b, model("Bass PODxt Live", basspodxt_live_data_model(), 16, 4)),
+        (0x000500, model("Floor POD Plus", floorpod_data_model(), 16, 4)),
+        (0x000600, model("Pocket POD", pocketpod_data_model(), 31, 4)),
+    ])
+}
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| RwLock::new(builtin_data_models()))
+}
+
+fn leak_info(name: &str, model: &'static DataModel, alias_of: Option<u32>,
+             capacity: Option<Capacity>) -> &'static DataModelInfo {
+    let name = Box::leak(name.to_string().into_boxed_str());
+    Box::leak(Box::new(DataModelInfo { name, model, alias_of, capacity }))
+}
+
+/// All registered data models, sorted by device id
+pub(crate) fn data_models() -> Vec<(u32, &'static DataModelInfo)> {
+    let registry = registry().read().unwrap();
+    let mut models = registry.iter()
+        .map(|(id, info)| (*id, *info))
+        .collect::<Vec<_>>();
+    models.sort_by_key(|(id, _)| *id);
+
+    models
+}
+
+/// Register a data model for a new device id. Registered data models
+/// live for the rest of the program, same as the built-in ones.
+pub fn register_data_model(id: u32, name: &str, model: DataModel) -> Result<&'static DataModel, RegistryError> {
+    let mut registry = registry().write().unwrap();
+    if registry.contains_key(&id) {
+        return Err(RegistryError::AlreadyRegistered(id));
+    }
+    let model = Box::leak(Box::new(model));
+    registry.insert(id, leak_info(name, model, None, None));
+
+    Ok(model)
+}
+
+/// Register a data model for a device id, replacing the data model
+/// registered for the id, if any. Aliases of the replaced data model
+/// are updated to point to the new one. The capacity of the device is
+/// kept. Returns the replaced data model.
+pub fn replace_data_model(id: u32, name: &str, model: DataModel) -> Option<&'static DataModelInfo> {
+    let mut registry = registry().write().unwrap();
+    let model: &'static DataModel = Box::leak(Box::new(model));
+    let capacity = registry.get(&id).and_then(|info| info.capacity);
+    let prev = registry.insert(id, leak_info(name, model, None, capacity));
+
+    let aliases = registry.iter()
+        .filter(|(_, info)| info.alias_of == Some(id))
+        .map(|(alias_id, info)| (*alias_id, info.name, info.capacity))
+        .collect::<Vec<_>>();
+    for (alias_id, name, capacity) in aliases {
+        registry.insert(alias_id, leak_info(name, model, Some(id), capacity));
+    }
+
+    prev
+}
+
+/// Register device id `id` as an alias of the data model registered
+/// for device id `target`, replacing the data model registered for
+/// `id`, if any, but keeping the capacity of the device.
+pub fn alias_data_model(id: u32, name: &str, target: u32) -> Result<(), RegistryError> {
+    let mut registry = registry().write().unwrap();
+    let target_info = *registry.get(&target)
+        .ok_or(RegistryError::NotRegistered(target))?;
+    // alias the original data model, not another alias
+    let alias_of = target_info.alias_of.unwrap_or(target);
+    let capacity = registry.get(&id).and_then(|info| info.capacity);
+    registry.insert(id, leak_info(name, target_info.model, Some(alias_of), capacity));
+
+    Ok(())
+}
+
+/// Set the user program memory of the device registered for the id
+pub fn set_capacity(id: u32, capacity: Option<Capacity>) -> Result<(), RegistryError> {
+    let mut registry = registry().write().unwrap();
+    let info = *registry.get(&id)
+        .ok_or(RegistryError::NotRegistered(id))?;
+    registry.insert(id, leak_info(info.name, info.model, info.alias_of, capacity));
+
+    Ok(())
+}
+
+pub fn data_model_keys() -> Vec<u32> {
+    data_models().into_iter()
+        .map(|(id, _)| id)
+        .collect()
 }
 
 pub fn data_model_info_by_id(id: u32) -> Option<&'static DataModelInfo> {
-    data_models()
+    registry().read().unwrap()
         .get(&id)
+        .cloned()
 }
 
 pub fn data_model_by_id(id: u32) -> Option<&'static DataModel> {
-    data_models()
-        .get(&id)
+    data_model_info_by_id(id)
         .map(|i| i.model)
 }
 
 pub fn data_model_by_num(num: usize) -> Option<&'static DataModel> {
-    data_model_keys()
+    data_models()
         .get(num)
-        .and_then(|key| data_model_by_id(*key))
+        .map(|(_, info)| info.model)
+}
+
+/// Reverse look-up of the device id the data model is registered with.
+/// When the same data model is registered for several ids, the one it
+/// is not an alias for is preferred, otherwise the highest id wins.
+pub fn data_model_id(model: &DataModel) -> Option<u32> {
+    let models = data_models();
+    let mut ids = models.iter().rev()
+        .filter(|(_, info)| std::ptr::eq(info.model, model));
+    let first = ids.clone().next().map(|(id, _)| *id);
+    ids.find(|(_, info)| info.alias_of.is_none())
+        .map(|(id, _)| *id)
+        .or(first)
 }
 
 pub fn filter_groups<F>(groups: &Vec<Group>, filter_fn: F) -> Vec<Group>
@@ -162,7 +297,8 @@ pub fn filter_params_by_prefix(model: &DataModel,
         _ => None
     });
 
-    let info_map = model.info_map.iter().flat_map(|(key, value)| {
+    // info map, defaults and packs are keyed by param name
+    let new_key = |key: &String| {
         if should_remove(key) {
             return None;
         }
@@ -172,12 +308,20 @@ pub fn filter_params_by_prefix(model: &DataModel,
             key.clone()
         };
 
-        Some((key, value.clone()))
-    }).collect();
+        Some(key)
+    };
 
     DataModel {
         floats_as_ints: model.floats_as_ints,
         groups,
-        info_map
+        info_map: model.info_map.iter()
+            .flat_map(|(key, value)| Some((new_key(key)?, value.clone())))
+            .collect(),
+        packs: model.packs.iter()
+            .flat_map(|(key, value)| Some((new_key(key)?, value.clone())))
+            .collect(),
+        defaults: model.defaults.iter()
+            .flat_map(|(key, value)| Some((new_key(key)?, value.clone())))
+            .collect()
     }
 }
@@ -4,7 +4,8 @@ use std::sync::OnceLock;
 use maplit::{convert_args, hashmap};
 use crate::data::pod2::{compression_ratio, effect_select, reverb_type, rotary_speed};
 use crate::data::shorthand::*;
-use crate::model::{DataModel, Group, Slot};
+use crate::model::{DataModel, Group, PackMap, Slot};
+use crate::value::ValueMap;
 
 /* NOTES: Vyzex does not save "wah enable" to a L6T,
    it is also a MIDI-only control.
@@ -393,7 +394,9 @@ pub fn pocketpod_data_model() -> &'static DataModel {
         DataModel {
             floats_as_ints: false,
             groups,
-            info_map
+            info_map,
+            packs: PackMap::new(),
+            defaults: ValueMap::new()
         }
     })
 }
@@ -4,7 +4,8 @@ use std::string::String;
 use std::sync::OnceLock;
 use maplit::{convert_args, hashmap};
 use crate::data::shorthand::*;
-use crate::model::{DataModel, Group, Slot};
+use crate::model::{DataModel, Group, PackMap, Slot};
+use crate::value::ValueMap;
 
 fn amp_select() -> &'static HashMap<u32, String> {
     static SELECT: OnceLock<HashMap<u32, String>> = OnceLock::new();
@@ -433,7 +434,9 @@ pub fn pod2_data_model() -> &'static DataModel {
         DataModel {
             floats_as_ints: true,
             groups,
-            info_map
+            info_map,
+            packs: PackMap::new(),
+            defaults: ValueMap::new()
         }
     })
 }
@@ -4,7 +4,9 @@ use std::sync::OnceLock;
 use maplit::{convert_args, hashmap};
 use crate::data::shorthand::*;
 use crate::data::models::filter_params_by_prefix;
-use crate::model::{DataModel, Group, Slot};
+use crate::model::{DataModel, Derive, Group, ModelPack, PackMap, Param, Slot};
+use crate::rich::{FormattingType, ValueInfoMap};
+use crate::value::{Value, ValueMap};
 
 
 pub(super) fn amp_select() -> &'static HashMap<u32, String> {
@@ -282,6 +284,37 @@ pub(super) fn note_select() -> &'static HashMap<u32, String> {
     })
 }
 
+/// Note lengths of `note_select` in quarter-note beats, "off" is not synced
+pub(super) fn note_beats() -> &'static HashMap<u32, f32> {
+    static MAP: OnceLock<HashMap<u32, f32>> = OnceLock::new();
+    MAP.get_or_init(|| {
+        HashMap::from([
+            (1, 4.0),
+            (2, 3.0),
+            (3, 2.0),
+            (4, 4.0 / 3.0),
+            (5, 1.5),
+            (6, 1.0),
+            (7, 2.0 / 3.0),
+            (8, 0.75),
+            (9, 0.5),
+            (10, 1.0 / 3.0),
+            (11, 0.375),
+            (12, 0.25),
+            (13, 1.0 / 6.0),
+        ])
+    })
+}
+
+pub(super) fn synced_delay_time() -> Param {
+    derived("delay_effective_time", Derive::SyncedTime {
+        tempo: "tempo".into(),
+        note: "delay_note_select".into(),
+        time: "delay_time".into(),
+        beats: note_beats()
+    })
+}
+
 pub(super) fn wave_value() -> &'static Vec<(u32, String)> {
     static MAP: OnceLock<Vec<(u32, String)>> = OnceLock::new();
     MAP.get_or_init(|| {
@@ -515,6 +548,12 @@ pub(super) fn mod_slot5(select: u32, model: u32, params: &[Option<&str>; 5]) ->
             param(4, 0x010001), // param 5
             ignore_f(0x010002),
             int(0x200000, "mod_note_select"),
+            derived("mod_effective_speed", Derive::SyncedRate {
+                tempo: "tempo".into(),
+                note: "mod_note_select".into(),
+                rate: "mod_speed".into(),
+                beats: note_beats()
+            }),
         ]
     }
 }
@@ -548,6 +587,7 @@ fn delay_slot(select: u32, model: u32, params: &[Option<&str>; 4]) -> Slot {
             param(3, 0x010001), // param 4
             float(0x010002, "di_xover"),
             int(0x200000, "delay_note_select"),
+            synced_delay_time(),
         ]
     }
 }
@@ -1135,7 +1175,7 @@ fn podxt_data_model_all() -> &'static DataModel {
             "gate_decay" => percent(),
 
             "comp_threshold" => db().convert(63.0, 0.0, -63.0).range(-63.0, 0.0),
-            "comp_gain" => db().convert(16.0, 0.0, 0.0).range(-63.0, 0.0),
+            "comp_gain" => db().convert(16.0, 0.0, 0.0).range(0.0, 16.0),
 
             "stomp_select" => lookup(stomp_select()),
             "stomp_drive" => percent(),
@@ -1171,6 +1211,7 @@ fn podxt_data_model_all() -> &'static DataModel {
             "mod_select" => lookup(mod_select()),
             "mod_speed" => hz().from_to(0.0, 0.1, 1.0, 15.0),
             "mod_note_select" => lookup(note_select()),
+            "mod_effective_speed" => hz(),
             "mod_depth" => percent(),
             "mod_bass" => percent(),
             "mod_treble" => percent(),
@@ -1188,6 +1229,7 @@ fn podxt_data_model_all() -> &'static DataModel {
             "delay_select" => lookup(delay_select()),
             "delay_time" => millis().from_to(0.0, 20.0, 1.0, 2000.0),
             "delay_note_select" => lookup(note_select()),
+            "delay_effective_time" => millis(),
             "delay_feedback" => percent(),
             "delay_bass" => percent(),
             "delay_treble" => percent(),
@@ -1236,14 +1278,41 @@ fn podxt_data_model_all() -> &'static DataModel {
             "live.footswitch_mode" => lookup(footswitch_mode_select()),
         ));
 
+        let packs = packs_by_prefix(&info_map);
         DataModel {
             floats_as_ints: false,
             groups,
-            info_map
+            info_map,
+            packs,
+            defaults: ValueMap::from([
+                ("amp_select".into(), Value::Int(131098)), // Plexi 45
+                ("amp_enable".into(), Value::Bool(true)),
+                ("cab_select".into(), Value::Int(16908306)), // 4x12 Green 20's
+                ("tempo".into(), Value::Float(120.0)),
+            ])
         }
     })
 }
 
+/// Model packs of PODxt lookup entries, the names of pack models start
+/// with the pack prefix ("MS-", "CC-", "FX-", "BX-")
+fn packs_by_prefix(info_map: &ValueInfoMap) -> PackMap {
+    let mut packs = PackMap::new();
+    for (name, info) in info_map {
+        let FormattingType::IntLookup(map) = info.formatting_type else { continue };
+        let entries = map.iter()
+            .flat_map(|(key, label)| {
+                let pack = ModelPack::ALL.into_iter().find(|p| label.starts_with(p.prefix()))?;
+                Some((*key, pack))
+            })
+            .collect::<HashMap<_, _>>();
+        if !entries.is_empty() {
+            packs.insert(name.clone(), entries);
+        }
+    }
+    packs
+}
+
 pub fn podxt_data_model() -> &'static DataModel {
     static MODEL: OnceLock<DataModel> = OnceLock::new();
     MODEL.get_or_init(||
@@ -1,5 +1,5 @@
 use std::collections::HashMap;
-use crate::model::{Param, ParamType};
+use crate::model::{Derive, Param, ParamType};
 use crate::rich::{Conversion, FormattingType, Range, ValueInfo};
 
 pub fn slot(name: &str) -> Param {
@@ -66,6 +66,13 @@ pub fn ignore_f(id: u32) -> Param {
     }
 }
 
+pub fn derived(name: &str, derive: Derive) -> Param {
+    Param::Derived {
+        name: name.into(),
+        derive
+    }
+}
+
 //
 
 pub fn lookup(map: &'static HashMap<u32, String>) -> ValueInfoBuilder {
@@ -3,7 +3,9 @@ use std::sync::OnceLock;
 
 use regex::Regex;
 
-use crate::data::{data_models, DataModelInfo};
+use crate::data::{data_model_by_id, data_model_info_by_id, data_models, DataModelInfo};
+use crate::rich::enrich_values;
+use crate::value::{Value, ValueMap};
 
 const OK_PARAMS: &[&str] = &[
     // known booleans
@@ -93,3 +95,288 @@ fn test_param_info_maps() {
     assert!(nok_models.is_empty(),
             "Errors in {} data models: {:?}", nok_models.len(), nok_models);
 }
+
+#[test]
+fn test_choices() {
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let amps = podxt.choices("amp_select").unwrap();
+    assert_eq!(amps.first().unwrap().label, "No Amp");
+    assert!(amps.iter().any(|c| c.label == "BX-Tube Preamp"));
+    assert!(amps.windows(2).all(|w| matches!((&w[0].value, &w[1].value), (Value::Int(a), Value::Int(b)) if a < b)));
+
+    let bass = data_model_by_id(0x030006).unwrap();
+    let amps = bass.choices("amp_select").unwrap();
+    assert!(amps.iter().all(|c| !c.label.starts_with("BX-")));
+    assert!(amps.iter().all(|c| podxt.choices("amp_select").unwrap().iter()
+        .any(|p| p.value == c.value) || c.label == "Bypass"));
+
+    // float lookup choices map back to their labels
+    let bits = podxt.choices("delay_bits").unwrap();
+    for choice in bits {
+        let rich = enrich_values(ValueMap::from([("delay_bits".into(), choice.value)]), &podxt.info_map);
+        assert_eq!(rich["delay_bits"].to_string(), choice.label);
+    }
+
+    assert!(podxt.choices("drive").is_none());
+    assert!(podxt.choices("no_such_param").is_none());
+}
+
+#[cfg(feature = "external")]
+#[test]
+fn test_external_data_model() {
+    use crate::data::external::DataModelDef;
+    use crate::model::Param;
+    use crate::rich::FormattingType;
+
+    let def = DataModelDef::from_toml(r#"
+        base = 0x030002
+
+        [[slot_params]]
+        fixed_slot = 0x30000
+        params = [ { type = "float", id = 0x100008, name = "amp_presence" } ]
+
+        [info.amp_presence]
+        format = "percent"
+
+        [extend_lookups.amp_select]
+        0x020099 = "Secret Amp"
+    "#).unwrap();
+    let ext = def.build().unwrap();
+    assert_eq!(ext.id, 0x030002);
+    assert!(matches!(ext.model.find_param("amp_presence"), Some(Param::Param { param_id: 0x100008, .. })));
+    let FormattingType::IntLookup(map) = ext.model.info_map["amp_select"].formatting_type else { panic!() };
+    assert_eq!(map[&0x020099], "Secret Amp");
+    assert_eq!(map[&131098], "Plexi 45");
+
+    let def = DataModelDef::from_json(r#"{
+        "id": "0x123456",
+        "name": "Test Device",
+        "lookups": { "amps": { "0": "Clean", "1": "Dirty" } },
+        "groups": [ { "name": "Amp", "slots": [ {
+            "fixed_slot": "0x30000",
+            "params": [
+                { "type": "slot", "name": "amp_select" },
+                { "type": "slot_enable", "name": "amp_enable" },
+                { "type": "float", "id": "0x100003", "name": "drive" },
+                { "type": "ignore", "id": 1048580, "float": true }
+            ]
+        } ] } ],
+        "info": {
+            "amp_select": { "format": "lookup", "lookup": "amps" },
+            "drive": { "format": "decibel", "decimals": 1, "from_to": [0, -12, 1, 12] }
+        }
+    }"#).unwrap();
+    let ext = def.build().unwrap();
+    assert_eq!(ext.id, 0x123456);
+    assert_eq!(ext.model.groups[0].slots[0].params.len(), 4);
+    assert_eq!(ext.model.choices("amp_select").unwrap().len(), 2);
+    assert!(ext.model.info_map["drive"].range.is_some());
+}
+
+#[test]
+fn test_registry() {
+    use crate::data::{alias_data_model, data_model_id, data_model_info_by_id, data_model_keys,
+                      register_data_model, replace_data_model, RegistryError};
+    use crate::model::DataModel;
+
+    let copy = |model: &DataModel| DataModel {
+        floats_as_ints: model.floats_as_ints,
+        groups: model.groups.clone(),
+        info_map: model.info_map.clone(),
+        packs: model.packs.clone(),
+        defaults: model.defaults.clone()
+    };
+    let podxt = data_model_by_id(0x030002).unwrap();
+
+    // built-in alias
+    assert_eq!(data_model_info_by_id(0x000200).unwrap().alias_of, Some(0x000300));
+    assert_eq!(data_model_id(data_model_by_id(0x000200).unwrap()), Some(0x000300));
+
+    let model = register_data_model(0x7f0001, "Test", copy(podxt)).unwrap();
+    assert!(matches!(register_data_model(0x7f0001, "Test", copy(podxt)),
+                     Err(RegistryError::AlreadyRegistered(0x7f0001))));
+    assert!(data_model_keys().contains(&0x7f0001));
+    assert_eq!(data_model_id(model), Some(0x7f0001));
+
+    alias_data_model(0x7f0000, "Test alias", 0x7f0001).unwrap();
+    assert!(std::ptr::eq(data_model_by_id(0x7f0000).unwrap(), model));
+    assert_eq!(data_model_id(model), Some(0x7f0001));
+
+    let prev = replace_data_model(0x7f0001, "Test 2", copy(podxt)).unwrap();
+    assert!(std::ptr::eq(prev.model, model));
+    let model = data_model_by_id(0x7f0001).unwrap();
+    assert!(std::ptr::eq(data_model_by_id(0x7f0000).unwrap(), model));
+    assert_eq!(data_model_info_by_id(0x7f0001).unwrap().name, "Test 2");
+
+    assert!(matches!(alias_data_model(0x7f0002, "Nothing", 0x7f0003),
+                     Err(RegistryError::NotRegistered(0x7f0003))));
+}
+
+#[cfg(feature = "serde")]
+#[test]
+fn test_json_schema() {
+    use crate::schema::json_schema;
+
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let schema = json_schema(podxt, "PODxt");
+    let props = &schema["properties"];
+    assert_eq!(props["delay_time"]["type"], "number");
+    assert_eq!(props["delay_time"]["x-unit"], "ms");
+    assert_eq!(props["delay_time"]["minimum"], 0.0);
+    assert_eq!(props["delay_time"]["maximum"], 1.0);
+    assert_eq!(props["delay_effective_time"]["readOnly"], true);
+    assert_eq!(props["mod_position"]["enum"], serde_json::json!([0, 1]));
+    assert!(props["amp_select"]["oneOf"].as_array().unwrap().iter()
+        .any(|c| c["const"] == 131098 && c["title"] == "Plexi 45"));
+
+    // the data model itself serializes, float lookups as key/value pairs
+    let json = serde_json::to_value(podxt).unwrap();
+    assert_eq!(json["info_map"]["delay_bits"]["formatting_type"]["FloatLookup"][0]["key"], 0);
+}
+
+#[test]
+fn test_lint_data_models() {
+    use crate::lint::lint_data_model;
+
+    let mut nok_models = vec![];
+    for (id, model_info) in data_models() {
+        if model_info.alias_of.is_some() { continue }
+        let issues = lint_data_model(model_info.model);
+        if !issues.is_empty() {
+            eprintln!("Data model: {} ({:#x}) ------------------------", model_info.name, id);
+            for issue in &issues {
+                eprintln!("  {}", issue);
+            }
+            nok_models.push(model_info.name);
+        }
+    }
+    assert!(nok_models.is_empty(),
+            "Lint issues in {} data models: {:?}", nok_models.len(), nok_models);
+}
+
+#[test]
+fn test_lint_issues() {
+    use std::collections::HashMap;
+    use maplit::{convert_args, hashmap};
+    use crate::data::shorthand::*;
+    use crate::lint::{lint_data_model, LintIssue};
+    use crate::model::{DataModel, Group, Param, ParamType, Slot};
+
+    static SELECT: OnceLock<HashMap<u32, String>> = OnceLock::new();
+    let select = SELECT.get_or_init(|| HashMap::from([(0, "A".into()), (1, "B".into())]));
+    let slot = |params| Slot { fixed_slot: Some(0x30000), fixed_model: None, fixed_enable: None, params };
+    let model = DataModel {
+        floats_as_ints: false,
+        groups: vec![Group {
+            name: "Test".into(),
+            slots: vec![
+                slot(vec![fixed_int("select", 0), fixed_int("select", 2), float(0x100000, "a"), float(0x100000, "b")]),
+                slot(vec![Param::FixedParam {
+                    name: "fixed".into(), param_value: 0, param_type: ParamType::Float, slot_id: None
+                }]),
+            ]
+        }],
+        info_map: convert_args!(hashmap!(
+            "select" => lookup(select),
+            "a" => hz().points(&[(0.0, 1.0), (0.5, 3.0), (1.0, 2.0)]),
+        )),
+        packs: Default::default(),
+        defaults: Default::default()
+    };
+
+    let issues = lint_data_model(&model);
+    assert_eq!(issues.len(), 6, "{:?}", issues);
+    assert!(matches!(issues[0], LintIssue::AmbiguousSlots { first: 0, second: 1, .. }));
+    assert!(matches!(issues[1], LintIssue::DuplicateParamId { slot: 0, param_id: 0x100000, .. }));
+    assert!(matches!(issues[2], LintIssue::UnsupportedFixedParam { .. }));
+    assert!(matches!(&issues[3], LintIssue::NonMonotonicPoints { name } if name == "a"));
+    assert!(matches!(&issues[4], LintIssue::UncoveredLookupKey { name, key: 1 } if name == "select"));
+    assert!(matches!(&issues[5], LintIssue::MissingLookupKey { name, value: 2 } if name == "select"));
+}
+
+#[test]
+fn test_labels() {
+    use crate::label::{catalog, description, label, register_catalog, Catalog};
+
+    assert_eq!(label("di_xover", "en"), "DI crossover");
+    assert!(description("di_xover", "en").is_some());
+    assert_eq!(label("variax_bass.blend", "en"), "Variax Bass pickup blend");
+    assert_eq!(label("stomp_drive", "en"), "Stomp drive");
+    assert_eq!(description("stomp_drive", "en"), None);
+
+    let mut xx = Catalog::new();
+    xx.insert("tempo", "Tempo (xx)", None);
+    register_catalog("xx", xx);
+    assert_eq!(label("tempo", "xx"), "Tempo (xx)");
+    // falls back to English
+    assert_eq!(label("di_xover", "xx"), "DI crossover");
+
+    // every built-in label is for a known param
+    let names = data_models().into_iter()
+        .flat_map(|(_, info)| &info.model.groups)
+        .flat_map(|g| &g.slots)
+        .flat_map(|s| &s.params)
+        .filter_map(|p| p.get_name())
+        .collect::<HashSet<_>>();
+    for key in catalog("en").unwrap().keys() {
+        assert!(names.contains(key), "unknown param {}", key);
+    }
+}
+
+#[test]
+fn test_program_names() {
+    let podxt = data_model_info_by_id(0x030002).unwrap().capacity.unwrap();
+    assert_eq!(podxt.program_name(11, 2).as_deref(), Some("12C"));
+    assert_eq!(podxt.program_name(2, 0).as_deref(), Some("03A"));
+    assert_eq!(podxt.program_name(16, 0), None);
+    assert_eq!(podxt.parse_program("12C"), Some((11, 2)));
+    assert_eq!(podxt.parse_program("03A"), Some((2, 0)));
+    assert_eq!(podxt.parse_program("3 a"), Some((2, 0)));
+    assert_eq!(podxt.parse_program("17A"), None);
+    assert_eq!(podxt.parse_program("1E"), None);
+    assert_eq!(podxt.parse_program("0A"), None);
+    assert_eq!(podxt.program_number(11, 2), Some(46));
+    assert_eq!(podxt.program_position(46), Some((11, 2)));
+    assert_eq!(podxt.program_position(64), None);
+
+    let pod2 = data_model_info_by_id(0x000300).unwrap().capacity.unwrap();
+    assert_eq!(pod2.program_name(4, 1).as_deref(), Some("5B"));
+    assert_eq!(pod2.parse_program("5B"), Some((4, 1)));
+
+    for (id, info) in data_models() {
+        let Some(capacity) = info.capacity else { continue };
+        for n in 0 .. capacity.total() {
+            let (bank, patch) = capacity.program_position(n).unwrap();
+            let name = capacity.program_name(bank, patch).unwrap();
+            assert_eq!(capacity.parse_program(&name), Some((bank, patch)), "{:#x} {}", id, name);
+        }
+    }
+}
+
+#[test]
+fn test_filter_params_by_prefix() {
+    use std::collections::HashMap;
+    use crate::data::models::filter_params_by_prefix;
+    use crate::data::shorthand::{float, int};
+    use crate::model::{DataModel, Group, ModelPack, Slot};
+
+    let slot = Slot {
+        fixed_slot: Some(0x10000),
+        fixed_model: Some(0x1),
+        fixed_enable: None,
+        params: vec![int(0x1, "live.select"), float(0x2, "pro.level")]
+    };
+    let pack = HashMap::from([(1, ModelPack::FxJunkie)]);
+    let model = DataModel {
+        floats_as_ints: false,
+        groups: vec![Group { name: "Test".into(), slots: vec![slot] }],
+        info_map: HashMap::new(),
+        packs: HashMap::from([("live.select".into(), pack.clone()), ("pro.select".into(), pack)]),
+        defaults: ValueMap::from([("live.select".into(), Value::Int(1)), ("pro.level".into(), Value::Float(0.5))])
+    };
+
+    let live = filter_params_by_prefix(&model, &["pro."], &["live."]);
+    assert_eq!(live.packs.keys().collect::<Vec<_>>(), vec!["select"]);
+    assert_eq!(live.defaults.keys().collect::<Vec<_>>(), vec!["select"]);
+    assert!(live.find_param("select").is_some());
+}
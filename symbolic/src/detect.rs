@@ -0,0 +1,120 @@
+use file::model::L6Patch;
+use crate::data::{data_model_by_id, data_models};
+use crate::model::DataModel;
+use crate::value::model_matches_slot;
+
+/// A registered data model scored against a patch
+pub struct Candidate {
+    pub id: u32,
+    pub name: &'static str,
+    pub model: &'static DataModel,
+    /// Share of patch params (plus params expected by the data model but
+    /// missing from the patch) that the data model knows, 0.0 to 1.0
+    pub confidence: f32,
+    /// Patch models that match a slot of the data model
+    pub matched_models: usize,
+    /// Patch models that match no slot of the data model
+    pub unmatched_models: usize,
+    /// Patch params the matching slots don't define
+    pub unprocessed_params: usize,
+    /// Params the matching slots define that the patch doesn't have
+    pub missing_params: usize,
+}
+
+fn score(patch: &L6Patch, id: u32, name: &'static str, model: &'static DataModel) -> Candidate {
+    let mut matched_models = 0;
+    let mut unmatched_models = 0;
+    let mut known_params = 0;
+    let mut unprocessed_params = 0;
+    let mut missing_params = 0;
+    let mut total_params = 0;
+
+    for patch_model in &patch.models {
+        total_params += patch_model.params.len();
+        let slot = model.groups.iter()
+            .flat_map(|g| &g.slots)
+            .find(|s| model_matches_slot(patch_model, s));
+        let Some(slot) = slot else {
+            unmatched_models += 1;
+            unprocessed_params += patch_model.params.len();
+            continue;
+        };
+        matched_models += 1;
+
+        let slot_ids = slot.params.iter()
+            .flat_map(|p| p.get_id())
+            .collect::<Vec<_>>();
+        for param in &patch_model.params {
+            if slot_ids.contains(&param.param_id) {
+                known_params += 1;
+            } else {
+                unprocessed_params += 1;
+            }
+        }
+        missing_params += slot_ids.iter()
+            .filter(|id| !patch_model.params.iter().any(|p| p.param_id == **id))
+            .count();
+    }
+
+    let confidence = if total_params + missing_params > 0 {
+        known_params as f32 / (total_params + missing_params) as f32
+    } else if !patch.models.is_empty() {
+        matched_models as f32 / patch.models.len() as f32
+    } else {
+        0.0
+    };
+
+    Candidate {
+        id, name, model, confidence,
+        matched_models, unmatched_models, unprocessed_params, missing_params
+    }
+}
+
+/// Score every registered data model against the patch, best candidates
+/// first. Data models registered under several device ids are scored
+/// once, under the id they are not an alias of. Candidates with the same
+/// confidence are ordered by fewer missing params, then by matching the
+/// device id in the patch header.
+pub fn detect_data_model(patch: &L6Patch) -> Vec<Candidate> {
+    let header_id = patch.target_device.midi_id;
+    let header_model = data_model_by_id(header_id);
+
+    let mut candidates = data_models().into_iter()
+        .filter(|(_, info)| info.alias_of.is_none())
+        .map(|(id, info)| score(patch, id, info.name, info.model))
+        .collect::<Vec<_>>();
+
+    let is_header = |c: &Candidate| header_model.is_some_and(|m| std::ptr::eq(m, c.model));
+    candidates.sort_by(|a, b| {
+        b.confidence.total_cmp(&a.confidence)
+            .then(a.missing_params.cmp(&b.missing_params))
+            .then(is_header(b).cmp(&is_header(a)))
+            .then(a.id.cmp(&b.id))
+    });
+
+    candidates
+}
+
+/// The data model registered for the device id in the patch header,
+/// unless another data model fits the patch better. Returns `None` if
+/// no data model matches any part of the patch.
+pub fn select_data_model(patch: &L6Patch) -> Option<Candidate> {
+    let header_model = data_model_by_id(patch.target_device.midi_id);
+    let mut candidates = detect_data_model(patch);
+    if candidates.is_empty() {
+        return None;
+    }
+
+    let best = candidates.remove(0);
+    if header_model.is_some_and(|m| std::ptr::eq(m, best.model)) {
+        return Some(best);
+    }
+    let header = header_model.and_then(|m| {
+        candidates.into_iter().find(|c| std::ptr::eq(m, c.model))
+    });
+    match header {
+        Some(header) if header.confidence >= best.confidence => Some(header),
+        _ if best.matched_models > 0 => Some(best),
+        _ => None
+    }
+}
@@ -0,0 +1,290 @@
+//! Patch fingerprints and duplicate detection.
+//!
+//! A fingerprint is a hash of the canonical content of a patch: the
+//! values read with the data model of the device, sorted by name and
+//! without derived values, so that param order and ignored params make
+//! no difference. Floats are rounded to steps of 1/127, the resolution
+//! of the device knobs (see `points_l6e`), to drop the noise editors
+//! leave in saved values. Meta tags and the patch name count only if
+//! requested. Patches of devices without a data model fall back to
+//! their raw models and params, as do the models and params the data
+//! model does not know.
+use std::collections::{HashMap, HashSet};
+use std::fmt::{Display, Formatter};
+use file::model::{L6Patch, Model, ModelParam, Value as L6Value};
+use crate::data::data_model_by_id;
+use crate::model::Param;
+use crate::value::{read_values, Diagnostic, Value};
+
+#[cfg(test)]
+mod tests;
+
+/// Float resolution of the devices
+const STEPS: f32 = 127.0;
+
+#[derive(Clone, Copy, Debug, Default)]
+pub struct FingerprintOptions {
+    /// Include the patch name and meta tags
+    pub include_meta: bool,
+    /// Group patches whose float values differ by at most this many
+    /// 1/127 steps, 0 groups only patches with the same fingerprint
+    pub tolerance: u32
+}
+
+/// A canonical value, floats are in 1/127 steps
+#[derive(Clone, Debug, PartialEq, Eq, Hash)]
+pub enum CanonicalValue {
+    Bool(bool),
+    Int(u32),
+    Steps(i64),
+    String(String)
+}
+
+/// Canonical content of a patch
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct CanonicalPatch {
+    pub device_id: u32,
+    /// Values sorted by name; raw values of patches of devices without a
+    /// data model and of models and params unknown to the data model are
+    /// named "<slot id>:<model id>:<param id>", or "<slot id>:<model id>"
+    /// for the enable of a model
+    pub values: Vec<(String, CanonicalValue)>,
+    /// Patch name and meta tags, if included
+    pub meta: Vec<String>
+}
+
+/// Stable 64-bit hash of the canonical content of a patch
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Fingerprint(pub u64);
+
+impl Display for Fingerprint {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+fn steps(v: f32) -> i64 {
+    (v * STEPS).round() as i64
+}
+
+fn canonical_value(value: &Value) -> CanonicalValue {
+    match value {
+        Value::Bool(v) => CanonicalValue::Bool(*v),
+        Value::Int(v) => CanonicalValue::Int(*v),
+        Value::Float(v) => CanonicalValue::Steps(steps(*v)),
+        Value::String(v) => CanonicalValue::String(v.clone())
+    }
+}
+
+fn raw_param(m: &Model, p: &ModelParam) -> (String, CanonicalValue) {
+    let value = match p.value {
+        L6Value::Int(v) => CanonicalValue::Int(v),
+        L6Value::Float(v) => CanonicalValue::Steps(steps(v))
+    };
+    (format!("{:x}:{:x}:{:x}", m.slot_id, m.model_id, p.param_id), value)
+}
+
+fn raw_model(m: &Model) -> impl Iterator<Item = (String, CanonicalValue)> + '_ {
+    let enabled = (format!("{:x}:{:x}", m.slot_id, m.model_id), CanonicalValue::Bool(m.enabled));
+    std::iter::once(enabled).chain(m.params.iter().map(move |p| raw_param(m, p)))
+}
+
+fn raw_values(patch: &L6Patch) -> Vec<(String, CanonicalValue)> {
+    patch.models.iter()
+        .flat_map(raw_model)
+        .collect()
+}
+
+/// Raw values of the models and params the data model does not know
+fn unprocessed_values(patch: &L6Patch, diagnostics: &[Diagnostic]) -> Vec<(String, CanonicalValue)> {
+    let find_model = |d: &Diagnostic| patch.models.iter()
+        .find(|m| m.slot_id == d.slot_id() && m.model_id == d.model_id() && m.ordinal == d.ordinal());
+    diagnostics.iter()
+        .flat_map(|d| {
+            let model = find_model(d);
+            let values: Vec<_> = match d {
+                Diagnostic::UnprocessedModel { .. } =>
+                    model.into_iter().flat_map(raw_model).collect(),
+                Diagnostic::UnprocessedParam { param_id, .. } =>
+                    model.into_iter()
+                        .flat_map(|m| m.params.iter()
+                            .filter(|p| p.param_id == *param_id)
+                            .map(move |p| raw_param(m, p)))
+                        .collect(),
+                _ => vec![]
+            };
+            values
+        })
+        .collect()
+}
+
+pub fn canonical_patch(patch: &L6Patch, options: &FingerprintOptions) -> CanonicalPatch {
+    let device_id = patch.target_device.midi_id;
+    let mut values = match data_model_by_id(device_id) {
+        Some(model) => {
+            let (values, diagnostics) = read_values(patch, model);
+            // derived values follow from the others
+            let derived = model.groups.iter()
+                .flat_map(|g| &g.slots)
+                .flat_map(|s| &s.params)
+                .filter_map(|p| match p {
+                    Param::Derived { name, .. } => Some(name),
+                    _ => None
+                })
+                .collect::<HashSet<_>>();
+            values.iter()
+                .filter(|(name, _)| !derived.contains(name))
+                .map(|(name, value)| (name.clone(), canonical_value(value)))
+                .chain(unprocessed_values(patch, &diagnostics))
+                .collect()
+        }
+        None => raw_values(patch)
+    };
+    values.sort_by(|a, b| a.0.cmp(&b.0));
+
+    let meta = if options.include_meta {
+        let m = &patch.meta;
+        vec![
+            patch.target_device.name.clone(),
+            m.author.clone(), m.guitarist.clone(), m.band.clone(), m.song.clone(),
+            m.style.clone(), m.pickup_style.clone(), m.pickup_position.clone(),
+            m.date.to_string(), m.amp_name.clone(), m.creator_app.clone(),
+            m.creator_app_version.clone(), m.comments.clone()
+        ]
+    } else {
+        vec![]
+    };
+
+    CanonicalPatch { device_id, values, meta }
+}
+
+/// FNV-1a, stable across platforms and releases, unlike the std hasher
+struct Fnv(u64);
+
+impl Fnv {
+    fn write(&mut self, bytes: &[u8]) {
+        for b in bytes {
+            self.0 ^= *b as u64;
+            self.0 = self.0.wrapping_mul(0x100000001b3);
+        }
+    }
+
+    /// Length prefixed, so that e.g. "ab" + "c" and "a" + "bc" differ
+    fn write_str(&mut self, s: &str) {
+        self.write(&(s.len() as u64).to_le_bytes());
+        self.write(s.as_bytes());
+    }
+}
+
+impl CanonicalPatch {
+    pub fn fingerprint(&self) -> Fingerprint {
+        self.hash(true)
+    }
+
+    /// Hash of everything but the float values
+    fn shape(&self) -> Fingerprint {
+        self.hash(false)
+    }
+
+    fn hash(&self, with_floats: bool) -> Fingerprint {
+        let mut h = Fnv(0xcbf29ce484222325);
+        h.write(&self.device_id.to_le_bytes());
+        for (name, value) in &self.values {
+            h.write_str(name);
+            match value {
+                CanonicalValue::Bool(v) => h.write(&[0, *v as u8]),
+                CanonicalValue::Int(v) => { h.write(&[1]); h.write(&v.to_le_bytes()) }
+                CanonicalValue::Steps(v) if with_floats => { h.write(&[2]); h.write(&v.to_le_bytes()) }
+                CanonicalValue::Steps(_) => h.write(&[2]),
+                CanonicalValue::String(v) => { h.write(&[3]); h.write_str(v) }
+            }
+        }
+        for s in &self.meta {
+            h.write(&[0xff]);
+            h.write_str(s);
+        }
+        Fingerprint(h.0)
+    }
+
+    /// Largest difference of float values in 1/127 steps, `None` if the
+    /// patches differ in anything else
+    pub fn distance(&self, other: &CanonicalPatch) -> Option<u32> {
+        if self.device_id != other.device_id || self.meta != other.meta ||
+            self.values.len() != other.values.len() {
+            return None;
+        }
+        let mut max = 0;
+        for ((n1, v1), (n2, v2)) in self.values.iter().zip(&other.values) {
+            if n1 != n2 { return None }
+            match (v1, v2) {
+                (CanonicalValue::Steps(a), CanonicalValue::Steps(b)) =>
+                    max = max.max(a.abs_diff(*b).min(u32::MAX as u64) as u32),
+                _ if v1 == v2 => {}
+                _ => return None
+            }
+        }
+        Some(max)
+    }
+}
+
+pub fn fingerprint(patch: &L6Patch, options: &FingerprintOptions) -> Fingerprint {
+    canonical_patch(patch, options).fingerprint()
+}
+
+/// Group identical or, with a tolerance, near-identical patches. Returns
+/// the indices of the patches of every group with more than one patch,
+/// in the order of their first patch.
+pub fn find_duplicates<'a>(patches: impl IntoIterator<Item = &'a L6Patch>, options: &FingerprintOptions) -> Vec<Vec<usize>> {
+    let canonical = patches.into_iter()
+        .map(|p| canonical_patch(p, options))
+        .collect::<Vec<_>>();
+
+    // exact duplicates
+    let mut groups: Vec<Vec<usize>> = vec![];
+    let mut by_fingerprint: HashMap<Fingerprint, usize> = HashMap::new();
+    for (n, c) in canonical.iter().enumerate() {
+        let group = *by_fingerprint.entry(c.fingerprint()).or_insert_with(|| {
+            groups.push(vec![]);
+            groups.len() - 1
+        });
+        groups[group].push(n);
+    }
+
+    // near duplicates, only patches of the same shape can be near
+    if options.tolerance > 0 {
+        let mut by_shape: HashMap<Fingerprint, Vec<usize>> = HashMap::new();
+        for (g, group) in groups.iter().enumerate() {
+            by_shape.entry(canonical[group[0]].shape()).or_default().push(g);
+        }
+        let mut merged_into = (0 .. groups.len()).collect::<Vec<_>>();
+        let root = |merged_into: &Vec<usize>, mut g: usize| {
+            while merged_into[g] != g { g = merged_into[g] }
+            g
+        };
+        for similar in by_shape.values() {
+            for (i, &a) in similar.iter().enumerate() {
+                for &b in &similar[i + 1 ..] {
+                    let near = canonical[groups[a][0]].distance(&canonical[groups[b][0]])
+                        .is_some_and(|d| d <= options.tolerance);
+                    if near {
+                        let (ra, rb) = (root(&merged_into, a), root(&merged_into, b));
+                        merged_into[ra.max(rb)] = ra.min(rb);
+                    }
+                }
+            }
+        }
+        let mut merged: Vec<Vec<usize>> = vec![vec![]; groups.len()];
+        for (g, group) in groups.iter().enumerate() {
+            merged[root(&merged_into, g)].extend(group);
+        }
+        groups = merged;
+        for group in &mut groups {
+            group.sort();
+        }
+        groups.sort_by_key(|g| g.first().copied());
+    }
+
+    groups.retain(|g| g.len() > 1);
+    groups
+}
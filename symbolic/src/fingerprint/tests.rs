@@ -0,0 +1,73 @@
+use file::model::{MetaTags, Model, ModelParam, Value as L6Value};
+use crate::data::data_model_by_id;
+use crate::fingerprint::{canonical_patch, fingerprint, find_duplicates, CanonicalPatch, CanonicalValue, FingerprintOptions};
+use crate::value::{write_values, Value, ValueMap};
+
+#[test]
+fn test_find_duplicates() {
+    let podxt = data_model_by_id(0x030002).unwrap();
+    // values saved by the devices are on the 1/127 grid
+    let values = podxt.default_values().into_iter()
+        .map(|(name, value)| match value {
+            Value::Float(v) => (name, Value::Float((v * 127.0).round() / 127.0)),
+            v => (name, v)
+        })
+        .collect::<ValueMap>();
+    let patch = |values: &ValueMap, name: &str| {
+        let mut patch = write_values(values, podxt, MetaTags::default()).unwrap();
+        patch.target_device.name = name.into();
+        patch
+    };
+    let original = patch(&values, "Original");
+
+    // same values, params in a different order, float noise, another name
+    let mut reupload = patch(&values, "Re-upload");
+    for model in &mut reupload.models {
+        model.params.reverse();
+        for param in &mut model.params {
+            if let L6Value::Float(v) = &mut param.value {
+                *v += 0.001;
+            }
+        }
+    }
+    let options = FingerprintOptions::default();
+    assert_eq!(fingerprint(&original, &options), fingerprint(&reupload, &options));
+    let with_meta = FingerprintOptions { include_meta: true, ..Default::default() };
+    assert_ne!(fingerprint(&original, &with_meta), fingerprint(&reupload, &with_meta));
+
+    // a knob turned by one step
+    let mut tweaked = values.clone();
+    let Some(Value::Float(drive)) = values.get("drive") else { panic!("expected a float drive") };
+    tweaked.insert("drive".into(), Value::Float(drive + 1.0 / 127.0));
+    let tweaked = patch(&tweaked, "Tweaked");
+
+    let mut other = values.clone();
+    other.insert("amp_select".into(), Value::Int(131104));
+    let other = patch(&other, "Other");
+
+    let patches = [original, other, reupload, tweaked];
+    assert_eq!(find_duplicates(&patches, &options), vec![vec![0, 2]]);
+    let near = FingerprintOptions { tolerance: 1, ..Default::default() };
+    assert_eq!(find_duplicates(&patches, &near), vec![vec![0, 2, 3]]);
+    assert_eq!(find_duplicates(&patches, &with_meta), Vec::<Vec<usize>>::new());
+
+    // params and models unknown to the data model count with their raw values
+    let mut unknown_param = patches[0].clone();
+    unknown_param.models[0].params.push(ModelParam { param_id: 0xfff, value: L6Value::Int(1) });
+    let unknown = canonical_patch(&unknown_param, &options);
+    let name = format!("{:x}:{:x}:fff", unknown_param.models[0].slot_id, unknown_param.models[0].model_id);
+    assert!(unknown.values.contains(&(name, CanonicalValue::Int(1))));
+    let mut unknown_model = patches[0].clone();
+    unknown_model.models.push(Model { slot_id: 0xfff, model_id: 0x123, ..Default::default() });
+    assert_eq!(find_duplicates([&patches[0], &unknown_param, &unknown_model], &options), Vec::<Vec<usize>>::new());
+
+    // strings are length prefixed, values can't run into the next name
+    let canonical = |values: Vec<(&str, CanonicalValue)>| CanonicalPatch {
+        device_id: 0,
+        values: values.into_iter().map(|(n, v)| (n.to_string(), v)).collect(),
+        meta: vec![]
+    };
+    let split = canonical(vec![("a", CanonicalValue::String("b".into())), ("c", CanonicalValue::Bool(true))]);
+    let joined = canonical(vec![("a", CanonicalValue::String("bc\u{0}\u{1}".into()))]);
+    assert_ne!(split.fingerprint(), joined.fingerprint());
+}
@@ -0,0 +1,115 @@
+//! Display labels and descriptions of params and groups.
+//!
+//! Param names (`amp_bypass_volume`, `variax_bass.blend`) are keys,
+//! not meant to be shown to users. A `Catalog` maps keys to labels and
+//! short descriptions in one language. Catalogs are registered by
+//! language code, English is built in. Keys missing from a catalog fall
+//! back to English, then to a label made from the key itself.
+use std::collections::HashMap;
+use std::sync::{OnceLock, RwLock};
+use crate::data::english_catalog;
+
+/// Display label and description of a param or group
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Label {
+    pub label: String,
+    pub description: Option<String>
+}
+
+/// Labels of params and groups in one language
+#[derive(Clone, Default)]
+pub struct Catalog {
+    labels: HashMap<String, Label>
+}
+
+impl Catalog {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn insert(&mut self, key: &str, label: &str, description: Option<&str>) {
+        let label = Label { label: label.into(), description: description.map(|d| d.into()) };
+        self.labels.insert(key.into(), label);
+    }
+
+    pub fn get(&self, key: &str) -> Option<&Label> {
+        self.labels.get(key)
+    }
+
+    pub fn keys(&self) -> impl Iterator<Item = &String> {
+        self.labels.keys()
+    }
+
+    /// Add all labels of the other catalog, replacing existing ones
+    pub fn extend(&mut self, other: Catalog) {
+        self.labels.extend(other.labels);
+    }
+}
+
+type Registry = HashMap<String, &'static Catalog>;
+
+fn registry() -> &'static RwLock<Registry> {
+    static REGISTRY: OnceLock<RwLock<Registry>> = OnceLock::new();
+    REGISTRY.get_or_init(|| {
+        let en: &'static Catalog = Box::leak(Box::new(english_catalog()));
+        RwLock::new(HashMap::from([("en".to_string(), en)]))
+    })
+}
+
+/// Register labels for a language. Labels of a language that already
+/// has a catalog are added to it, replacing labels with the same keys.
+pub fn register_catalog(lang: &str, catalog: Catalog) {
+    let mut registry = registry().write().unwrap();
+    let mut merged = registry.get(lang).map(|c| (*c).clone()).unwrap_or_default();
+    merged.extend(catalog);
+    registry.insert(lang.into(), Box::leak(Box::new(merged)));
+}
+
+pub fn catalog(lang: &str) -> Option<&'static Catalog> {
+    registry().read().unwrap().get(lang).copied()
+}
+
+/// Languages with a registered catalog, sorted
+pub fn languages() -> Vec<String> {
+    let mut langs = registry().read().unwrap().keys().cloned().collect::<Vec<_>>();
+    langs.sort();
+    langs
+}
+
+/// Language of the user from the `LANG` environment variable, e.g.
+/// "de" for "de_DE.UTF-8", English if not set
+pub fn system_language() -> String {
+    std::env::var("LANG").ok()
+        .and_then(|v| v.split(['_', '.']).next().map(|l| l.to_lowercase()))
+        .filter(|l| !l.is_empty() && l != "c" && l != "posix")
+        .unwrap_or_else(|| "en".into())
+}
+
+fn find(key: &str, lang: &str) -> Option<&'static Label> {
+    catalog(lang).and_then(|c| c.get(key))
+        .or_else(|| catalog("en").and_then(|c| c.get(key)))
+}
+
+/// Label made from the key: "variax_bass.blend" becomes
+/// "Variax bass blend"
+fn key_label(key: &str) -> String {
+    let words = key.replace(['_', '.'], " ");
+    let mut chars = words.chars();
+    match chars.next() {
+        Some(c) => c.to_uppercase().chain(chars).collect(),
+        None => String::new()
+    }
+}
+
+/// Display label of a param or group
+pub fn label(key: &str, lang: &str) -> String {
+    find(key, lang)
+        .map(|l| l.label.clone())
+        .unwrap_or_else(|| key_label(key))
+}
+
+/// Short description of a param or group, if the catalog has one
+pub fn description(key: &str, lang: &str) -> Option<String> {
+    find(key, lang).and_then(|l| l.description.clone())
+}
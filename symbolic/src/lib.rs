@@ -3,3 +3,19 @@ pub mod model;
 pub mod value;
 pub mod group;
 pub mod rich;
+pub mod validate;
+pub mod detect;
+pub mod fingerprint;
+pub mod lint;
+pub mod bundle;
+pub mod chain;
+pub mod label;
+pub mod pack;
+#[cfg(any(test, feature = "testing"))]
+pub mod random;
+pub mod setup;
+pub mod library;
+pub mod query;
+pub mod report;
+#[cfg(feature = "serde")]
+pub mod schema;
@@ -0,0 +1,216 @@
+//! Indexing patch libraries.
+//!
+//! A `Library` holds the patches of every supported file of a directory
+//! tree: patches, bundles and collections. Every
+//! entry keeps the values read with the data model of the patch, so that
+//! entries can be searched with a `Query`. Files that fail to decode are
+//! listed in `errors` instead of failing the scan. SoundDiver libraries
+//! are not indexed, their patches aren't decoded yet.
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use thiserror::Error;
+use file::decoder::{Decoder, DecoderResult};
+use file::model::{Bank, L6Patch, MetaTags};
+use crate::bundle::bundle_capacity;
+use crate::data::data_model_info_by_id;
+use crate::detect::select_data_model;
+use crate::model::DataModel;
+use crate::query::Query;
+use crate::value::{read_values, ValueMap};
+
+#[cfg(test)]
+mod tests;
+
+/// File extensions of the supported files, compared ignoring case
+pub const EXTENSIONS: [&str; 3] = ["l6t", "l6b", "l6c"];
+
+#[derive(Error, Debug)]
+pub enum LibraryError {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Failed to decode: {0}")]
+    Decode(String)
+}
+
+/// A patch of a library file
+pub struct Entry {
+    pub path: PathBuf,
+    /// Bank of a bundle, nested folders of collections are named by path,
+    /// e.g. "Artist/Album"
+    pub bank: Option<String>,
+    /// Position of the patch in the file
+    pub index: usize,
+    /// Device program of a patch of a bundle, e.g. "12C"
+    pub program: Option<String>,
+    /// Device name, `None` for unknown devices
+    pub device: Option<&'static str>,
+    /// Data model the values were read with, `None` if no data model
+    /// matches the patch
+    pub model: Option<&'static DataModel>,
+    pub values: ValueMap,
+    /// Meta tags of the patch, empty tags filled in from the folder, its
+    /// parent folders and the bundle
+    pub meta: MetaTags,
+    pub patch: L6Patch
+}
+
+impl Entry {
+    fn new(path: &Path, bank: Option<String>, index: usize, program: Option<String>,
+           patch: L6Patch, inherited: &[&MetaTags]) -> Self {
+        let device = data_model_info_by_id(patch.target_device.midi_id).map(|info| info.name);
+        let candidate = select_data_model(&patch);
+        let device = device.or(candidate.as_ref().map(|c| c.name));
+        let model = candidate.map(|c| c.model);
+        let values = model.map(|m| read_values(&patch, m).0).unwrap_or_default();
+        let meta = inherited.iter().fold(patch.meta.clone(), |meta, from| inherit_meta(meta, from));
+
+        Entry { path: path.to_path_buf(), bank, index, program, device, model, values, meta, patch }
+    }
+
+    pub fn name(&self) -> &str {
+        &self.patch.target_device.name
+    }
+
+    /// Text of a field other than a param: the patch name, device, file
+    /// path, bank, program or a meta tag. Returns `None` for params.
+    pub fn text_field(&self, field: &str) -> Option<String> {
+        let m = &self.meta;
+        let text = match field.to_lowercase().as_str() {
+            "name" => self.name().to_string(),
+            "device" => self.device.map_or_else(
+                || format!("{:#x}", self.patch.target_device.midi_id), str::to_string),
+            "file" => self.path.display().to_string(),
+            "bank" => self.bank.clone().unwrap_or_default(),
+            "program" => self.program.clone().unwrap_or_default(),
+            "author" => m.author.clone(),
+            "guitarist" => m.guitarist.clone(),
+            "band" => m.band.clone(),
+            "song" => m.song.clone(),
+            "style" => m.style.clone(),
+            "pickup_style" => m.pickup_style.clone(),
+            "pickup_position" => m.pickup_position.clone(),
+            "date" => m.date.to_string(),
+            "amp_name" => m.amp_name.clone(),
+            "creator_app" => m.creator_app.clone(),
+            "creator_app_version" => m.creator_app_version.clone(),
+            "comments" => m.comments.clone(),
+            _ => return None
+        };
+        Some(text)
+    }
+}
+
+fn inherit_meta(meta: MetaTags, from: &MetaTags) -> MetaTags {
+    let or = |tag: String, from: &String| if tag.is_empty() { from.clone() } else { tag };
+    MetaTags {
+        author: or(meta.author, &from.author),
+        guitarist: or(meta.guitarist, &from.guitarist),
+        band: or(meta.band, &from.band),
+        song: or(meta.song, &from.song),
+        style: or(meta.style, &from.style),
+        pickup_style: or(meta.pickup_style, &from.pickup_style),
+        pickup_position: or(meta.pickup_position, &from.pickup_position),
+        date: if meta.date == 0 { from.date } else { meta.date },
+        amp_name: or(meta.amp_name, &from.amp_name),
+        creator_app: or(meta.creator_app, &from.creator_app),
+        creator_app_version: or(meta.creator_app_version, &from.creator_app_version),
+        comments: or(meta.comments, &from.comments)
+    }
+}
+
+/// Fill in the empty tags of nested folders from their parent, down the
+/// tree
+fn inherit_folder_meta(bank: &mut Bank) {
+    for folder in &mut bank.folders {
+        if let Some(parent) = &bank.meta {
+            let meta = folder.meta.take().unwrap_or_default();
+            folder.meta = Some(inherit_meta(meta, parent));
+        }
+        inherit_folder_meta(folder);
+    }
+}
+
+#[derive(Default)]
+pub struct Library {
+    pub entries: Vec<Entry>,
+    /// Files that failed to read or decode
+    pub errors: Vec<(PathBuf, LibraryError)>
+}
+
+fn is_supported(path: &Path) -> bool {
+    path.extension()
+        .and_then(|e| e.to_str())
+        .is_some_and(|e| EXTENSIONS.iter().any(|s| e.eq_ignore_ascii_case(s)))
+}
+
+impl Library {
+    pub fn new() -> Self {
+        Default::default()
+    }
+
+    /// Index the supported files of the directory and its subdirectories,
+    /// in the order of their paths
+    pub fn scan(dir: &Path) -> Result<Self, LibraryError> {
+        let mut library = Library::new();
+        let mut files = vec![];
+        collect_files(dir, &mut files)?;
+        files.sort();
+        for path in files {
+            if let Err(e) = library.add_file(&path) {
+                library.errors.push((path, e));
+            }
+        }
+        Ok(library)
+    }
+
+    pub fn add_file(&mut self, path: &Path) -> Result<(), LibraryError> {
+        let data = fs::read(path)?;
+        self.add_data(path, &data)
+    }
+
+    /// Index the patches of the decoded data as the file at the path
+    pub fn add_data(&mut self, path: &Path, data: &[u8]) -> Result<(), LibraryError> {
+        let decoded = Decoder::read(data)
+            .map_err(|e| LibraryError::Decode(e.to_string()))?;
+        match decoded {
+            DecoderResult::Patch(patch) =>
+                self.entries.push(Entry::new(path, None, 0, None, patch, &[])),
+            DecoderResult::Bundle(bundle) => {
+                let capacity = bundle_capacity(&bundle);
+                let banks = bundle.banks.into_iter()
+                    .flat_map(|mut b| { inherit_folder_meta(&mut b); b.into_flat() });
+                let mut index = 0;
+                for (n, bank) in banks.enumerate() {
+                    let inherited = bank.meta.iter().chain(&bundle.meta).collect::<Vec<_>>();
+                    for (i, patch) in bank.patches.into_iter().enumerate() {
+                        let program = capacity.and_then(|c| c.program_name(n, i));
+                        let bank = Some(bank.name.clone());
+                        self.entries.push(Entry::new(path, bank, index, program, patch, &inherited));
+                        index += 1;
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+
+    /// Entries matching the query, in library order
+    pub fn search(&self, query: &Query) -> Vec<&Entry> {
+        self.entries.iter()
+            .filter(|e| query.matches(e))
+            .collect()
+    }
+}
+
+fn collect_files(dir: &Path, files: &mut Vec<PathBuf>) -> Result<(), io::Error> {
+    for entry in fs::read_dir(dir)? {
+        let path = entry?.path();
+        if path.is_dir() {
+            collect_files(&path, files)?;
+        } else if is_supported(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
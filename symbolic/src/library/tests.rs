@@ -0,0 +1,77 @@
+use std::path::Path;
+use file::encoder::Encoder;
+use file::model::{Bank, BundleType, L6Bundle, MetaTags};
+use crate::data::data_model_by_id;
+use crate::library::Library;
+use crate::query::{Query, QueryError};
+use crate::value::{write_values, Value};
+
+#[test]
+fn test_library_search() {
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let patch = |name: &str, amp: u32, delay: bool, band: &str| {
+        let mut values = podxt.default_values();
+        values.insert("amp_select".into(), Value::Int(amp));
+        values.insert("delay_enable".into(), Value::Bool(delay));
+        let meta = MetaTags { band: band.into(), ..Default::default() };
+        let mut patch = write_values(&values, podxt, meta).unwrap();
+        patch.target_device.name = name.into();
+        patch
+    };
+    let bundle = L6Bundle {
+        banks: vec![Bank {
+            name: "Gig".into(),
+            patches: vec![
+                patch("Comfortably", 131098, true, "Pink Floyd"),
+                patch("Dry Plexi", 131098, false, "Pink Floyd"),
+                patch("Brit", 131104, true, "Other")
+            ],
+            ..Default::default()
+        }],
+        meta: Some(MetaTags { author: "Me".into(), ..Default::default() }),
+        ..Default::default()
+    };
+    let mut live = patch("Live", 131104, false, "");
+    live.target_device.midi_id = 0x03000a;
+
+    let mut library = Library::new();
+    library.add_data(Path::new("gig.l6b"), &Encoder::write_bundle(&bundle).unwrap()).unwrap();
+    library.add_data(Path::new("live.l6t"), &Encoder::write(&live).unwrap()).unwrap();
+    assert!(library.add_data(Path::new("junk.l6t"), b"junk").is_err());
+
+    // tags of nested folders are filled in from every parent folder
+    let album = Bank {
+        name: "Album".into(),
+        patches: vec![patch("Nested", 131104, false, "")],
+        meta: Some(MetaTags { style: "Blues".into(), ..Default::default() }),
+        ..Default::default()
+    };
+    let artist = Bank {
+        name: "Artist".into(),
+        folders: vec![album],
+        meta: Some(MetaTags { guitarist: "Dave".into(), creator_app_version: "2.1".into(), ..Default::default() }),
+        ..Default::default()
+    };
+    let collection = L6Bundle { bundle_type: BundleType::Collection, banks: vec![artist], ..Default::default() };
+    library.add_data(Path::new("songs.l6c"), &Encoder::write_bundle(&collection).unwrap()).unwrap();
+
+    let search = |query: &str| library.search(&Query::parse(query).unwrap()).iter()
+        .map(|e| e.name().to_string())
+        .collect::<Vec<_>>();
+    assert_eq!(search(r#"amp_select = "Plexi 45" AND delay_enable"#), vec!["Comfortably"]);
+    assert_eq!(search("band ~ floyd AND NOT delay_enable"), vec!["Dry Plexi"]);
+    assert_eq!(search("device = PODxt Live"), vec!["Live"]);
+    assert_eq!(search("amp_select ~ plexi OR (name = live)"), vec!["Comfortably", "Dry Plexi", "Live"]);
+    assert_eq!(search("program = 01C"), vec!["Brit"]);
+    assert_eq!(search("author = me").len(), 3);
+    assert_eq!(library.entries[2].bank.as_deref(), Some("Gig"));
+
+    let nested = library.entries.last().unwrap();
+    assert_eq!(nested.bank.as_deref(), Some("Artist/Album"));
+    assert_eq!((nested.meta.style.as_str(), nested.meta.guitarist.as_str()), ("Blues", "Dave"));
+    assert_eq!(search("guitarist = dave AND creator_app_version = 2.1"), vec!["Nested"]);
+
+    assert_eq!(Query::parse("band ~"), Err(QueryError::UnexpectedEnd));
+    assert_eq!(Query::parse("(band"), Err(QueryError::UnexpectedEnd));
+    assert_eq!(Query::parse("name = \"x"), Err(QueryError::UnclosedQuote));
+}
@@ -0,0 +1,137 @@
+use std::collections::HashSet;
+use thiserror::Error;
+use crate::model::{DataModel, Param, ParamType, Slot};
+use crate::rich::{Conversion, FormattingType};
+
+/// A self-consistency problem of a data model
+#[derive(Error, Debug)]
+pub enum LintIssue {
+    #[error("Slots {first} and {second} of group \"{group}\" can match the same model")]
+    AmbiguousSlots { group: String, first: usize, second: usize },
+    #[error("Slot {slot} of group \"{group}\" has param id {param_id:#x} more than once")]
+    DuplicateParamId { group: String, slot: usize, param_id: u32 },
+    #[error("Lookup key {key:#x} of \"{name}\" selects no slot")]
+    UncoveredLookupKey { name: String, key: u32 },
+    #[error("Fixed param \"{name}\" selects a slot with value {value:#x}, which is not in its lookup")]
+    MissingLookupKey { name: String, value: u32 },
+    #[error("Interpolation points of \"{name}\" are not monotonic")]
+    NonMonotonicPoints { name: String },
+    #[error("Fixed param \"{name}\" of type {param_type:?} cannot be written")]
+    UnsupportedFixedParam { name: String, param_type: ParamType },
+}
+
+/// Slot ids a model must have to match the slot, see `model_matches_slot`
+fn slot_ids(slot: &Slot) -> HashSet<u32> {
+    match slot.fixed_slot {
+        Some(id) => HashSet::from([id]),
+        None => slot.params.iter()
+            .filter_map(|p| match p {
+                Param::FixedParam { slot_id, .. } => *slot_id,
+                _ => None
+            })
+            .collect()
+    }
+}
+
+fn compatible<T: PartialEq>(a: Option<T>, b: Option<T>) -> bool {
+    match (a, b) {
+        (Some(a), Some(b)) => a == b,
+        _ => true
+    }
+}
+
+/// Whether some patch model could match both slots in `model_matches_slot`
+fn slots_overlap(a: &Slot, b: &Slot) -> bool {
+    !slot_ids(a).is_disjoint(&slot_ids(b)) &&
+        compatible(a.fixed_model, b.fixed_model) &&
+        compatible(a.fixed_enable, b.fixed_enable)
+}
+
+fn is_monotonic(values: impl Iterator<Item = f32> + Clone) -> bool {
+    let pairs = values.clone().zip(values.skip(1));
+    let increasing = pairs.clone().all(|(a, b)| a <= b);
+    let decreasing = pairs.clone().all(|(a, b)| a >= b);
+    increasing || decreasing
+}
+
+/// Check the data model for problems that make reading or writing values
+/// unreliable: ambiguous slots, duplicate param ids, select lookup keys
+/// no slot is selected by, fixed param values missing from the lookup of
+/// the param, non-monotonic interpolations (that cannot be
+/// inverted) and fixed params `write_values` cannot encode.
+pub fn lint_data_model(model: &DataModel) -> Vec<LintIssue> {
+    let mut issues = vec![];
+
+    for group in &model.groups {
+        for (i, a) in group.slots.iter().enumerate() {
+            for (j, b) in group.slots.iter().enumerate().skip(i + 1) {
+                if slots_overlap(a, b) {
+                    issues.push(LintIssue::AmbiguousSlots { group: group.name.clone(), first: i, second: j });
+                }
+            }
+
+            let mut seen = HashSet::new();
+            for param_id in a.params.iter().filter_map(|p| p.get_id()) {
+                if !seen.insert(param_id) {
+                    issues.push(LintIssue::DuplicateParamId {
+                        group: group.name.clone(), slot: i, param_id
+                    });
+                }
+            }
+        }
+    }
+
+    let params = model.groups.iter()
+        .flat_map(|g| &g.slots)
+        .flat_map(|s| &s.params)
+        .collect::<Vec<_>>();
+
+    let mut reported = HashSet::new();
+    for param in &params {
+        let Param::FixedParam { name, param_type, .. } = param else { continue };
+        if *param_type != ParamType::Int && reported.insert(name) {
+            issues.push(LintIssue::UnsupportedFixedParam { name: name.clone(), param_type: param_type.clone() });
+        }
+    }
+
+    let mut names = model.info_map.keys().collect::<Vec<_>>();
+    names.sort();
+    for name in names {
+        let info = &model.info_map[name];
+        if let FormattingType::IntLookup(map) = info.formatting_type {
+            let fixed_values = params.iter()
+                .filter_map(|p| match p {
+                    Param::FixedParam { name: n, param_value, .. } if n == name => Some(*param_value),
+                    _ => None
+                })
+                .collect::<HashSet<_>>();
+            // only lookups of fixed params select slots, unless the
+            // value is also read from a param when no slot is selected
+            let also_param = params.iter()
+                .any(|p| matches!(p, Param::Param { name: n, .. } if n == name));
+            if !fixed_values.is_empty() && !also_param {
+                let mut keys = map.keys().filter(|k| !fixed_values.contains(k)).collect::<Vec<_>>();
+                keys.sort();
+                for key in keys {
+                    issues.push(LintIssue::UncoveredLookupKey { name: name.clone(), key: *key });
+                }
+            }
+            let mut missing = fixed_values.iter().filter(|v| !map.contains_key(v)).collect::<Vec<_>>();
+            missing.sort();
+            for value in missing {
+                issues.push(LintIssue::MissingLookupKey { name: name.clone(), value: *value });
+            }
+        }
+
+        if let Some(Conversion::Interpolate { points }) = &info.conversion {
+            let xs = points.iter().map(|(x, _)| *x);
+            let ys = points.iter().map(|(_, y)| *y);
+            let xs_increasing = xs.clone().zip(xs.skip(1)).all(|(a, b)| a < b);
+            if !xs_increasing || !is_monotonic(ys) {
+                issues.push(LintIssue::NonMonotonicPoints { name: name.clone() });
+            }
+        }
+    }
+
+    issues
+}
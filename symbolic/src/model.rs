@@ -1,18 +1,205 @@
-use crate::rich::ValueInfoMap;
+use std::collections::HashMap;
+use std::fmt::{Display, Formatter};
+use crate::rich::{float_lookup_value, FormattingType, ValueInfoMap};
+use crate::value::{Value, ValueMap};
 
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct DataModel {
     pub floats_as_ints: bool,
     pub groups: Vec<Group>,
-    pub info_map: ValueInfoMap
+    pub info_map: ValueInfoMap,
+    /// Model packs providing lookup entries, keyed by lookup param
+    /// name and entry key. Entries not listed are stock models.
+    pub packs: PackMap,
+    /// Default values of params, see `default_value` for params not
+    /// listed here
+    pub defaults: ValueMap
+}
+
+/// Optional model pack providing additional amps, cabs or effects
+#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+#[cfg_attr(feature = "external", derive(serde::Deserialize))]
+pub enum ModelPack {
+    MetalShop,
+    CollectorsClassics,
+    FxJunkie,
+    BassExpansion
+}
+
+impl ModelPack {
+    pub const ALL: [ModelPack; 4] = [
+        ModelPack::MetalShop, ModelPack::CollectorsClassics,
+        ModelPack::FxJunkie, ModelPack::BassExpansion
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ModelPack::MetalShop => "Metal Shop",
+            ModelPack::CollectorsClassics => "Collector's Classics",
+            ModelPack::FxJunkie => "FX Junkie",
+            ModelPack::BassExpansion => "Bass Expansion"
+        }
+    }
+
+    /// Prefix of the names of the pack models in PODxt lookups
+    pub fn prefix(&self) -> &'static str {
+        match self {
+            ModelPack::MetalShop => "MS-",
+            ModelPack::CollectorsClassics => "CC-",
+            ModelPack::FxJunkie => "FX-",
+            ModelPack::BassExpansion => "BX-"
+        }
+    }
+}
+
+impl Display for ModelPack {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+pub type PackMap = HashMap<String, HashMap<u32, ModelPack>>;
+
+impl DataModel {
+    /// Find the first param definition with the given name
+    pub fn find_param(&self, name: &str) -> Option<&Param> {
+        self.groups.iter()
+            .flat_map(|g| &g.slots)
+            .flat_map(|s| &s.params)
+            .find(|p| p.get_name().is_some_and(|n| n == name))
+    }
+
+    /// Default value of a named param: the value set in `defaults`,
+    /// otherwise the lowest alternative of fixed params, `false` for bools
+    /// and enables, the first choice of lookups, the minimum of the range
+    /// for ints and the middle of the range for floats. Params without a
+    /// range default to 0 or 0.5. Returns `None` for unknown and derived
+    /// params.
+    pub fn default_value(&self, name: &str) -> Option<Value> {
+        if let Some(value) = self.defaults.get(name) {
+            return Some(value.clone());
+        }
+        let params = self.groups.iter()
+            .flat_map(|g| &g.slots)
+            .flat_map(|s| &s.params)
+            .filter(|p| p.get_name().is_some_and(|n| n == name))
+            .collect::<Vec<_>>();
+        let fixed = params.iter()
+            .filter_map(|p| match p {
+                Param::FixedParam { param_value, .. } => Some(*param_value),
+                _ => None
+            })
+            .min();
+        if let Some(fixed) = fixed {
+            return Some(Value::Int(fixed));
+        }
+
+        let param_type = match params.first()? {
+            Param::Derived { .. } => return None,
+            Param::SlotEnable { .. } => return Some(Value::Bool(false)),
+            p => p.get_type()
+        };
+        if let Some(choice) = self.choices(name).and_then(|c| c.into_iter().next()) {
+            return Some(choice.value);
+        }
+        let middle = self.info_map.get(name)
+            .and_then(|info| {
+                let range = info.range.as_ref()?;
+                if !range.min.is_finite() || !range.max.is_finite() { return None }
+                let middle = match param_type {
+                    ParamType::Int => range.min,
+                    _ => (range.min + range.max) / 2.0
+                };
+                info.unconvert(middle as f64, &param_type).ok()
+            });
+        let value = match param_type {
+            ParamType::Bool => Value::Bool(false),
+            ParamType::Int => middle.unwrap_or(Value::Int(0)),
+            ParamType::Float => middle.unwrap_or(Value::Float(0.5)),
+        };
+        Some(value)
+    }
+
+    /// Default values of all named params, except derived ones
+    pub fn default_values(&self) -> ValueMap {
+        let mut values = ValueMap::new();
+        let names = self.groups.iter()
+            .flat_map(|g| &g.slots)
+            .flat_map(|s| &s.params)
+            .filter_map(|p| p.get_name());
+        for name in names {
+            if values.contains_key(name) { continue }
+            if let Some(value) = self.default_value(name) {
+                values.insert(name.clone(), value);
+            }
+        }
+        values
+    }
+
+    /// Ordered list of choices for a lookup param (`amp_select`,
+    /// `stomp_select`, etc.). Each data model carries its own lookups,
+    /// so the choices are those of the device variant, e.g. only the
+    /// "BX" amps for Bass PODxt. Returns `None` if the param is not a
+    /// lookup param of this data model.
+    pub fn choices(&self, name: &str) -> Option<Vec<Choice>> {
+        let param_type = self.find_param(name)?.get_type();
+        let info = self.info_map.get(name)?;
+        let choices = match info.formatting_type {
+            FormattingType::IntLookup(map) => {
+                let mut keys = map.keys().collect::<Vec<_>>();
+                keys.sort();
+                keys.into_iter()
+                    .flat_map(|k| {
+                        let value = info.unconvert(*k as f64, &param_type).ok()?;
+                        let pack = self.pack(name, *k);
+                        Some(Choice { value, label: map[k].clone(), pack })
+                    })
+                    .collect()
+            }
+            FormattingType::FloatLookup(map) => {
+                map.iter().enumerate()
+                    .flat_map(|(pos, (key, label))| {
+                        let converted = float_lookup_value(map, pos);
+                        let value = info.unconvert(converted, &param_type).ok()?;
+                        let pack = self.pack(name, *key);
+                        Some(Choice { value, label: label.clone(), pack })
+                    })
+                    .collect()
+            }
+            _ => return None
+        };
+
+        Some(choices)
+    }
+
+    /// Model pack providing the lookup entry, `None` for stock models
+    pub fn pack(&self, name: &str, key: u32) -> Option<ModelPack> {
+        self.packs.get(name)?.get(&key).copied()
+    }
+}
+
+/// One of the allowed values of a lookup param
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Choice {
+    /// Simple value to store in a `ValueMap`
+    pub value: Value,
+    /// Display name of the value
+    pub label: String,
+    /// Model pack the value needs, `None` for stock models
+    pub pack: Option<ModelPack>
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Group {
     pub name: String,
     pub slots: Vec<Slot>
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Slot {
     pub fixed_slot: Option<u32>,
     pub fixed_model: Option<u32>,
@@ -20,7 +207,8 @@ pub struct Slot {
     pub params: Vec<Param>
 }
 
-#[derive(Clone, Eq, PartialEq)]
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum ParamType {
     Int,
     Float,
@@ -28,6 +216,7 @@ pub enum ParamType {
 }
 
 #[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Param {
     SlotModel {
         name: String
@@ -49,6 +238,35 @@ pub enum Param {
     IgnoreParam {
         param_id: u32,
         param_type: ParamType,
+    },
+    /// A value computed from other values when reading a patch,
+    /// not written to the patch
+    Derived {
+        name: String,
+        derive: Derive
+    }
+}
+
+#[derive(Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub enum Derive {
+    /// Effective time in ms: the length of the note selected by `note`
+    /// at `tempo` BPM if tempo sync is on, the converted value of `time`
+    /// otherwise
+    SyncedTime {
+        tempo: String,
+        note: String,
+        time: String,
+        beats: &'static HashMap<u32, f32>
+    },
+    /// Effective rate in Hz: one cycle per the note selected by `note`
+    /// at `tempo` BPM if tempo sync is on, the converted value of `rate`
+    /// otherwise
+    SyncedRate {
+        tempo: String,
+        note: String,
+        rate: String,
+        beats: &'static HashMap<u32, f32>
     }
 }
 
@@ -61,13 +279,25 @@ impl Param {
         }
     }
 
+    pub fn get_type(&self) -> ParamType {
+        match self {
+            Param::SlotModel { .. } => ParamType::Int,
+            Param::SlotEnable { .. } => ParamType::Bool,
+            Param::Param { param_type, .. } => param_type.clone(),
+            Param::FixedParam { param_type, .. } => param_type.clone(),
+            Param::IgnoreParam { param_type, .. } => param_type.clone(),
+            Param::Derived { .. } => ParamType::Float
+        }
+    }
+
     pub fn get_name(&self) -> Option<&String> {
         match self {
             Param::SlotModel { name, .. } => Some(name),
             Param::SlotEnable { name, .. } => Some(name),
             Param::Param { name, .. } => Some(name),
             Param::FixedParam { name, .. } => Some(name),
-            Param::IgnoreParam { .. } => None
+            Param::IgnoreParam { .. } => None,
+            Param::Derived { name, .. } => Some(name)
         }
     }
 }
@@ -0,0 +1,82 @@
+//! Model packs needed by patches.
+//!
+//! Data models record which model pack provides a lookup entry, see
+//! `DataModel::packs`. The same entry may be a stock model of one device
+//! and need a pack on another, e.g. the "BX" amps are stock models of
+//! Bass PODxt, but need the Bass Expansion pack on PODxt.
+use file::model::L6Patch;
+use crate::data::data_models;
+use crate::model::{DataModel, ModelPack};
+use crate::rich::FormattingType;
+use crate::value::read_values;
+
+/// A value of the patch that selects a model of a model pack
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct PackUse {
+    /// Lookup param name, e.g. "amp_select"
+    pub name: String,
+    pub key: u32,
+    pub label: Option<String>,
+    pub pack: ModelPack
+}
+
+/// Device a lookup entry is available on
+#[derive(Clone, Debug, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize))]
+pub struct Availability {
+    pub id: u32,
+    pub name: &'static str,
+    /// Model pack the device needs for the entry, `None` for stock models
+    pub pack: Option<ModelPack>
+}
+
+fn lookup_label(model: &DataModel, name: &str, key: u32) -> Option<String> {
+    match model.info_map.get(name)?.formatting_type {
+        FormattingType::IntLookup(map) => map.get(&key).cloned(),
+        FormattingType::FloatLookup(vec) => vec.iter()
+            .find(|(k, _)| *k == key)
+            .map(|(_, label)| label.clone()),
+        _ => None
+    }
+}
+
+/// Values of the patch that select models of model packs, sorted by
+/// param name
+pub fn pack_uses(patch: &L6Patch, model: &DataModel) -> Vec<PackUse> {
+    let (values, _) = read_values(patch, model);
+    let mut uses = values.iter()
+        .flat_map(|(name, value)| {
+            let info = model.info_map.get(name)?;
+            let key = info.convert(value)? as u32;
+            let pack = model.pack(name, key)?;
+            let label = lookup_label(model, name, key);
+            Some(PackUse { name: name.clone(), key, label, pack })
+        })
+        .collect::<Vec<_>>();
+    uses.sort_by(|a, b| a.name.cmp(&b.name));
+
+    uses
+}
+
+/// Model packs the patch needs, sorted
+pub fn required_packs(patch: &L6Patch, model: &DataModel) -> Vec<ModelPack> {
+    let mut packs = pack_uses(patch, model).into_iter()
+        .map(|u| u.pack)
+        .collect::<Vec<_>>();
+    packs.sort();
+    packs.dedup();
+
+    packs
+}
+
+/// Registered devices that have the lookup entry, with the model pack
+/// each of them needs for it. Data models registered under several
+/// device ids are listed once, under the id they are not an alias of.
+pub fn availability(name: &str, key: u32) -> Vec<Availability> {
+    data_models().into_iter()
+        .filter(|(_, info)| info.alias_of.is_none())
+        .filter(|(_, info)| lookup_label(info.model, name, key).is_some())
+        .map(|(id, info)| Availability { id, name: info.name, pack: info.model.pack(name, key) })
+        .collect()
+}
@@ -0,0 +1,308 @@
+//! Queries over library entries.
+//!
+//! A query tests fields of a patch with operators and combines the tests
+//! with `AND`, `OR`, `NOT` and parentheses, e.g.
+//! `amp_select = "Plexi 45" AND delay_enable` or `band ~ Floyd`.
+//!
+//! Fields are param names, meta tags (`author`, `band`, `song`, etc.),
+//! `name`, `device`, `file`, `bank` and `program`. Operators are `=`,
+//! `!=`, `~` (contains, ignoring case), `<`, `<=`, `>` and `>=`. Param
+//! values are parsed the same way as `parse_value` does, e.g. "350 ms";
+//! values may be quoted or span several words, e.g. `device = PODxt Live`.
+//! A field without an operator tests if a param is on or not zero, or if
+//! a text field is not empty.
+use std::cmp::Ordering;
+use std::fmt::{Display, Formatter};
+use thiserror::Error;
+use crate::library::Entry;
+use crate::rich::{enrich_values, parse_value};
+use crate::value::{Value, ValueMap};
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Op {
+    Eq,
+    Ne,
+    Contains,
+    Lt,
+    Le,
+    Gt,
+    Ge
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum Query {
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+    Not(Box<Query>),
+    /// A field, optionally compared to a value
+    Test { field: String, cmp: Option<(Op, String)> }
+}
+
+#[derive(Error, Debug, PartialEq)]
+pub enum QueryError {
+    #[error("Unexpected end of query")]
+    UnexpectedEnd,
+    #[error("Unexpected '{0}'")]
+    Unexpected(String),
+    #[error("Unclosed quote")]
+    UnclosedQuote
+}
+
+#[derive(Clone, Debug, PartialEq)]
+enum Token {
+    Word(String),
+    Quoted(String),
+    Op(Op),
+    Open,
+    Close
+}
+
+impl Display for Token {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Token::Word(w) => write!(f, "{}", w),
+            Token::Quoted(w) => write!(f, "\"{}\"", w),
+            Token::Op(op) => write!(f, "{:?}", op),
+            Token::Open => write!(f, "("),
+            Token::Close => write!(f, ")")
+        }
+    }
+}
+
+fn tokenize(input: &str) -> Result<Vec<Token>, QueryError> {
+    let mut tokens = vec![];
+    let mut chars = input.chars().peekable();
+    while let Some(c) = chars.next() {
+        let token = match c {
+            _ if c.is_whitespace() => continue,
+            '(' => Token::Open,
+            ')' => Token::Close,
+            '~' => Token::Op(Op::Contains),
+            '=' => Token::Op(Op::Eq),
+            '!' | '<' | '>' => {
+                let eq = chars.next_if_eq(&'=').is_some();
+                match (c, eq) {
+                    ('!', true) => Token::Op(Op::Ne),
+                    ('!', false) => return Err(QueryError::Unexpected("!".into())),
+                    ('<', true) => Token::Op(Op::Le),
+                    ('<', false) => Token::Op(Op::Lt),
+                    ('>', true) => Token::Op(Op::Ge),
+                    _ => Token::Op(Op::Gt)
+                }
+            }
+            '"' => {
+                let mut s = String::new();
+                loop {
+                    match chars.next() {
+                        Some('"') => break,
+                        Some(c) => s.push(c),
+                        None => return Err(QueryError::UnclosedQuote)
+                    }
+                }
+                Token::Quoted(s)
+            }
+            _ => {
+                let mut s = c.to_string();
+                while let Some(c) = chars.next_if(|c| !c.is_whitespace() && !"()~=!<>\"".contains(*c)) {
+                    s.push(c);
+                }
+                Token::Word(s)
+            }
+        };
+        tokens.push(token);
+    }
+
+    Ok(tokens)
+}
+
+fn is_keyword(token: &Token, keyword: &str) -> bool {
+    matches!(token, Token::Word(w) if w.eq_ignore_ascii_case(keyword))
+}
+
+struct Parser {
+    tokens: Vec<Token>,
+    pos: usize
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.pos)
+    }
+
+    fn next(&mut self) -> Option<Token> {
+        let token = self.tokens.get(self.pos).cloned();
+        self.pos += 1;
+        token
+    }
+
+    fn accept(&mut self, keyword: &str) -> bool {
+        let found = self.peek().is_some_and(|t| is_keyword(t, keyword));
+        if found { self.pos += 1 }
+        found
+    }
+
+    fn or(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.and()?;
+        while self.accept("or") {
+            query = Query::Or(Box::new(query), Box::new(self.and()?));
+        }
+        Ok(query)
+    }
+
+    fn and(&mut self) -> Result<Query, QueryError> {
+        let mut query = self.not()?;
+        while self.accept("and") {
+            query = Query::And(Box::new(query), Box::new(self.not()?));
+        }
+        Ok(query)
+    }
+
+    fn not(&mut self) -> Result<Query, QueryError> {
+        if self.accept("not") {
+            return Ok(Query::Not(Box::new(self.not()?)));
+        }
+        self.atom()
+    }
+
+    fn atom(&mut self) -> Result<Query, QueryError> {
+        match self.next().ok_or(QueryError::UnexpectedEnd)? {
+            Token::Open => {
+                let query = self.or()?;
+                match self.next() {
+                    Some(Token::Close) => Ok(query),
+                    Some(t) => Err(QueryError::Unexpected(t.to_string())),
+                    None => Err(QueryError::UnexpectedEnd)
+                }
+            }
+            Token::Word(field) => {
+                let Some(&Token::Op(op)) = self.peek() else {
+                    return Ok(Query::Test { field, cmp: None });
+                };
+                self.pos += 1;
+                Ok(Query::Test { field, cmp: Some((op, self.value()?)) })
+            }
+            t => Err(QueryError::Unexpected(t.to_string()))
+        }
+    }
+
+    /// A quoted value or the words up to the next keyword or parenthesis
+    fn value(&mut self) -> Result<String, QueryError> {
+        if let Some(Token::Quoted(s)) = self.peek() {
+            let s = s.clone();
+            self.pos += 1;
+            return Ok(s);
+        }
+        let mut words = vec![];
+        while let Some(Token::Word(w)) = self.peek() {
+            if ["and", "or"].iter().any(|k| w.eq_ignore_ascii_case(k)) { break }
+            words.push(w.clone());
+            self.pos += 1;
+        }
+        if words.is_empty() {
+            return Err(self.next().map_or(QueryError::UnexpectedEnd, |t| QueryError::Unexpected(t.to_string())));
+        }
+        Ok(words.join(" "))
+    }
+}
+
+impl Query {
+    pub fn parse(input: &str) -> Result<Query, QueryError> {
+        let mut parser = Parser { tokens: tokenize(input)?, pos: 0 };
+        let query = parser.or()?;
+        match parser.next() {
+            None => Ok(query),
+            Some(t) => Err(QueryError::Unexpected(t.to_string()))
+        }
+    }
+
+    pub fn matches(&self, entry: &Entry) -> bool {
+        match self {
+            Query::And(a, b) => a.matches(entry) && b.matches(entry),
+            Query::Or(a, b) => a.matches(entry) || b.matches(entry),
+            Query::Not(q) => !q.matches(entry),
+            Query::Test { field, cmp } => test(entry, field, cmp.as_ref())
+        }
+    }
+}
+
+fn test(entry: &Entry, field: &str, cmp: Option<&(Op, String)>) -> bool {
+    if let Some(text) = entry.text_field(field) {
+        return match cmp {
+            None => !text.is_empty(),
+            Some((op, value)) => compare_text(&text, *op, value)
+        };
+    }
+    let Some(value) = entry.values.get(field) else {
+        return false;
+    };
+    match cmp {
+        None => is_on(value),
+        Some((op, input)) => compare_value(entry, field, value, *op, input)
+    }
+}
+
+fn compare_text(text: &str, op: Op, value: &str) -> bool {
+    let (text, value) = (text.to_lowercase(), value.to_lowercase());
+    let ord = match (text.parse::<f64>(), value.parse::<f64>()) {
+        (Ok(a), Ok(b)) => a.partial_cmp(&b),
+        _ => Some(text.cmp(&value))
+    };
+    match op {
+        Op::Contains => text.contains(&value),
+        _ => ord.is_some_and(|ord| is_ordered(ord, op))
+    }
+}
+
+fn is_on(value: &Value) -> bool {
+    match value {
+        Value::Bool(v) => *v,
+        Value::Int(v) => *v != 0,
+        Value::Float(v) => *v != 0.0,
+        Value::String(v) => !v.is_empty()
+    }
+}
+
+fn is_ordered(ord: Ordering, op: Op) -> bool {
+    match op {
+        Op::Eq => ord == Ordering::Equal,
+        Op::Ne => ord != Ordering::Equal,
+        Op::Lt => ord == Ordering::Less,
+        Op::Le => ord != Ordering::Greater,
+        Op::Gt => ord == Ordering::Greater,
+        Op::Ge => ord != Ordering::Less,
+        Op::Contains => false
+    }
+}
+
+/// The value as shown to the user, e.g. "Plexi 45" or "350 ms"
+fn display_value(entry: &Entry, name: &str, value: &Value) -> String {
+    let Some(model) = entry.model else {
+        return value.to_string();
+    };
+    let values = ValueMap::from([(name.to_string(), value.clone())]);
+    enrich_values(values, &model.info_map)[name].to_string()
+}
+
+fn compare_value(entry: &Entry, name: &str, value: &Value, op: Op, input: &str) -> bool {
+    if op == Op::Contains {
+        return display_value(entry, name, value).to_lowercase().contains(&input.to_lowercase());
+    }
+    let parsed = entry.model.and_then(|m| parse_value(name, input, m).ok());
+    let Some(parsed) = parsed else {
+        // not a value of the param, compare as shown
+        return compare_text(&display_value(entry, name, value), op, input);
+    };
+    let info = entry.model.and_then(|m| m.info_map.get(name));
+    let number = |v: &Value| match (info, v) {
+        (Some(info), _) => info.convert(v),
+        (None, Value::Int(v)) => Some(*v as f64),
+        (None, Value::Float(v)) => Some(*v as f64),
+        _ => None
+    };
+    let ord = match (value, &parsed) {
+        (Value::Float(a), Value::Float(b)) if (a - b).abs() < 0.5 / 127.0 => Some(Ordering::Equal),
+        _ if *value == parsed => Some(Ordering::Equal),
+        _ => number(value).zip(number(&parsed)).and_then(|(a, b)| a.partial_cmp(&b))
+    };
+    ord.is_some_and(|ord| is_ordered(ord, op))
+}
@@ -0,0 +1,86 @@
+//! Random values for round-trip testing.
+//!
+//! `random_values` makes a complete `ValueMap` for a data model, picking
+//! one of the alternatives of fixed params, lookup keys for lookup params
+//! and values within the range of params with a range. See
+//! `file::random` for random raw patches.
+use std::collections::HashMap;
+use file::random::Rng;
+use crate::model::{DataModel, Param, ParamType};
+use crate::value::{resolve_slot, Value, ValueMap};
+
+fn random_value(model: &DataModel, name: &str, param_type: &ParamType, rng: &mut Rng) -> Value {
+    if let Some(choices) = model.choices(name) {
+        if let Some(choice) = rng.choose(&choices) {
+            return choice.value.clone();
+        }
+    }
+    let ranged = model.info_map.get(name)
+        .and_then(|info| {
+            let range = info.range.as_ref()?;
+            if !range.min.is_finite() || !range.max.is_finite() { return None }
+            let converted = match param_type {
+                ParamType::Int => range.min + rng.below((range.max - range.min) as u32 + 1) as f32,
+                _ => range.min + (range.max - range.min) * rng.next_f32()
+            };
+            info.unconvert(converted as f64, param_type).ok()
+        });
+    if let Some(value) = ranged {
+        return value;
+    }
+
+    match param_type {
+        ParamType::Bool => Value::Bool(rng.next_bool()),
+        ParamType::Int => Value::Int(rng.below(128)),
+        ParamType::Float => Value::Float(rng.next_f32())
+    }
+}
+
+/// Random values for the named params of the data model, except derived
+/// ones. Only the params of the slots selected by the values of the fixed
+/// params are included, so that `read_values` reads back all of them from
+/// the patch made by `write_values`. The values are valid for
+/// `validate_values`.
+pub fn random_values(model: &DataModel, rng: &mut Rng) -> ValueMap {
+    let params = model.groups.iter()
+        .flat_map(|g| &g.slots)
+        .flat_map(|s| &s.params);
+
+    // fixed params select slots, pick one of the alternatives
+    let mut alternatives: HashMap<&String, Vec<u32>> = HashMap::new();
+    for param in params.clone() {
+        if let Param::FixedParam { name, param_value, .. } = param {
+            let values = alternatives.entry(name).or_default();
+            if !values.contains(param_value) {
+                values.push(*param_value);
+            }
+        }
+    }
+
+    let mut values = ValueMap::new();
+    for (name, alternatives) in &alternatives {
+        values.insert((*name).clone(), Value::Int(*rng.choose(alternatives).unwrap()));
+    }
+    for param in params {
+        let value = match param {
+            Param::SlotModel { name } | Param::Param { name, .. } => {
+                if values.contains_key(name) { continue }
+                (name, random_value(model, name, &param.get_type(), rng))
+            }
+            Param::SlotEnable { name } => (name, Value::Bool(rng.next_bool())),
+            _ => continue
+        };
+        values.entry(value.0.clone()).or_insert(value.1);
+    }
+
+    let selected = model.groups.iter()
+        .flat_map(|g| &g.slots)
+        .filter(|slot| matches!(resolve_slot(slot, &values), Ok(Some(_))))
+        .flat_map(|slot| &slot.params)
+        .filter_map(|p| p.get_name())
+        .collect::<Vec<_>>();
+    values.iter()
+        .filter(|(name, _)| alternatives.contains_key(name) || selected.contains(name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect()
+}
@@ -0,0 +1,225 @@
+//! Usage reports of patch libraries.
+//!
+//! A `Report` summarizes library entries per device: how often every
+//! amp, cab and effect model is used, how the values of every param are
+//! distributed and which params are never changed from their default.
+//! Only enabled blocks that select a model from a lookup, such as amps,
+//! cabs and effects, count as model usage. Authors and styles are
+//! counted over all entries. Reports print as text with `Display`, or as
+//! CSV with `to_csv`.
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::{Display, Formatter};
+use crate::chain::signal_chain;
+use crate::library::Entry;
+use crate::rich::enrich_values;
+use crate::value::Value;
+
+#[cfg(test)]
+mod tests;
+
+/// Number of most common values listed per param or model in text reports
+const TEXT_TOP: usize = 5;
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Count {
+    pub value: String,
+    pub count: usize
+}
+
+/// Counts of the values of a param or of the models of a block, most
+/// common first
+#[derive(Clone, Debug, PartialEq)]
+pub struct Distribution {
+    pub name: String,
+    pub counts: Vec<Count>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct DeviceReport {
+    pub device: String,
+    pub patches: usize,
+    /// Models of the blocks of the signal chain, e.g. "Amp" or "Delay"
+    pub models: Vec<Distribution>,
+    pub params: Vec<Distribution>,
+    /// Params with the default value in every patch
+    pub untouched: Vec<String>
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Report {
+    pub patches: usize,
+    pub devices: Vec<DeviceReport>,
+    pub authors: Vec<Count>,
+    pub styles: Vec<Count>
+}
+
+#[derive(Default)]
+struct DeviceCounts {
+    patches: usize,
+    models: BTreeMap<String, HashMap<String, usize>>,
+    params: BTreeMap<String, HashMap<String, usize>>,
+    touched: HashMap<String, bool>
+}
+
+fn is_default(value: &Value, default: &Value) -> bool {
+    match (value, default) {
+        (Value::Float(a), Value::Float(b)) => (a - b).abs() < 0.5 / 127.0,
+        _ => value == default
+    }
+}
+
+fn sorted_counts(counts: HashMap<String, usize>) -> Vec<Count> {
+    let mut counts = counts.into_iter()
+        .map(|(value, count)| Count { value, count })
+        .collect::<Vec<_>>();
+    counts.sort_by(|a, b| b.count.cmp(&a.count).then_with(|| a.value.cmp(&b.value)));
+    counts
+}
+
+fn distributions(map: BTreeMap<String, HashMap<String, usize>>) -> Vec<Distribution> {
+    map.into_iter()
+        .map(|(name, counts)| Distribution { name, counts: sorted_counts(counts) })
+        .collect()
+}
+
+fn add_device(counts: &mut DeviceCounts, entry: &Entry) {
+    counts.patches += 1;
+    let Some(model) = entry.model else { return };
+
+    for block in signal_chain(&entry.patch, model).into_iter().filter(|b| b.enabled) {
+        let Some(name) = block.model_name else { continue };
+        *counts.models.entry(block.name).or_default().entry(name).or_default() += 1;
+    }
+
+    // derived values are left out, they follow from the others
+    let defaults = model.default_values();
+    let values = entry.values.iter()
+        .filter(|(name, _)| defaults.contains_key(*name))
+        .map(|(name, value)| (name.clone(), value.clone()))
+        .collect();
+    for (name, value) in enrich_values(values, &model.info_map) {
+        let touched = !is_default(value.get_simple(), &defaults[&name]);
+        *counts.touched.entry(name.clone()).or_default() |= touched;
+        *counts.params.entry(name).or_default().entry(value.to_string()).or_default() += 1;
+    }
+    for name in defaults.into_keys() {
+        counts.touched.entry(name).or_default();
+    }
+}
+
+/// Report of the entries, e.g. all entries of a `Library` or the result
+/// of a search
+pub fn library_report<'a>(entries: impl IntoIterator<Item = &'a Entry>) -> Report {
+    let mut patches = 0;
+    let mut devices: BTreeMap<String, DeviceCounts> = BTreeMap::new();
+    let mut authors: HashMap<String, usize> = HashMap::new();
+    let mut styles: HashMap<String, usize> = HashMap::new();
+
+    for entry in entries {
+        patches += 1;
+        let device = entry.text_field("device").unwrap_or_default();
+        add_device(devices.entry(device).or_default(), entry);
+        for (tag, counts) in [(&entry.meta.author, &mut authors), (&entry.meta.style, &mut styles)] {
+            let tag = tag.trim();
+            if !tag.is_empty() {
+                *counts.entry(tag.to_string()).or_default() += 1;
+            }
+        }
+    }
+
+    let devices = devices.into_iter()
+        .map(|(device, counts)| {
+            let mut untouched = counts.touched.into_iter()
+                .filter(|(_, touched)| !touched)
+                .map(|(name, _)| name)
+                .collect::<Vec<_>>();
+            untouched.sort();
+            DeviceReport {
+                device,
+                patches: counts.patches,
+                models: distributions(counts.models),
+                params: distributions(counts.params),
+                untouched
+            }
+        })
+        .collect();
+
+    Report { patches, devices, authors: sorted_counts(authors), styles: sorted_counts(styles) }
+}
+
+fn write_counts(f: &mut Formatter<'_>, counts: &[Count]) -> std::fmt::Result {
+    let shown = counts.iter()
+        .take(TEXT_TOP)
+        .map(|c| format!("{} ({})", c.value, c.count))
+        .collect::<Vec<_>>();
+    write!(f, "{}", shown.join(", "))?;
+    if counts.len() > TEXT_TOP {
+        write!(f, ", {} more", counts.len() - TEXT_TOP)?;
+    }
+    writeln!(f)
+}
+
+impl Display for Report {
+    fn fmt(&self, f: &mut Formatter<'_>) -> std::fmt::Result {
+        writeln!(f, "Patches: {}", self.patches)?;
+        write!(f, "Authors: ")?;
+        write_counts(f, &self.authors)?;
+        write!(f, "Styles: ")?;
+        write_counts(f, &self.styles)?;
+        for device in &self.devices {
+            writeln!(f)?;
+            writeln!(f, "{}: {} patches", device.device, device.patches)?;
+            writeln!(f, "  Models:")?;
+            for d in &device.models {
+                write!(f, "    {:<16}: ", d.name)?;
+                write_counts(f, &d.counts)?;
+            }
+            writeln!(f, "  Values:")?;
+            for d in &device.params {
+                write!(f, "    {:<24}: ", d.name)?;
+                write_counts(f, &d.counts)?;
+            }
+            writeln!(f, "  Never changed: {}", device.untouched.join(", "))?;
+        }
+        Ok(())
+    }
+}
+
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+impl Report {
+    /// All counts as CSV with the columns "section", "device", "name",
+    /// "value" and "count". Sections are "model", "param", "untouched",
+    /// "author" and "style".
+    pub fn to_csv(&self) -> String {
+        let mut rows = vec![["section", "device", "name", "value", "count"].map(String::from)];
+        for device in &self.devices {
+            let dev = &device.device;
+            for (section, distributions) in [("model", &device.models), ("param", &device.params)] {
+                for d in distributions {
+                    for c in &d.counts {
+                        rows.push([section.into(), dev.clone(), d.name.clone(), c.value.clone(), c.count.to_string()]);
+                    }
+                }
+            }
+            for name in &device.untouched {
+                rows.push(["untouched".into(), dev.clone(), name.clone(), "".into(), device.patches.to_string()]);
+            }
+        }
+        for (section, counts) in [("author", &self.authors), ("style", &self.styles)] {
+            for c in counts {
+                rows.push([section.into(), "".into(), "".into(), c.value.clone(), c.count.to_string()]);
+            }
+        }
+
+        rows.iter()
+            .map(|row| row.iter().map(|s| csv_field(s)).collect::<Vec<_>>().join(",") + "\n")
+            .collect()
+    }
+}
@@ -0,0 +1,47 @@
+use std::path::Path;
+use file::encoder::Encoder;
+use file::model::MetaTags;
+use crate::data::data_model_by_id;
+use crate::library::Library;
+use crate::report::{library_report, Count};
+use crate::value::{write_values, Value};
+
+#[test]
+fn test_library_report() {
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let patch = |amp: u32, author: &str, style: &str| {
+        let mut values = podxt.default_values();
+        values.insert("amp_select".into(), Value::Int(amp));
+        values.insert("amp_enable".into(), Value::Bool(true));
+        let meta = MetaTags { author: author.into(), style: style.into(), ..Default::default() };
+        Encoder::write(&write_values(&values, podxt, meta).unwrap()).unwrap()
+    };
+    let mut library = Library::new();
+    library.add_data(Path::new("a.l6t"), &patch(131098, "Me", "Rock, Blues")).unwrap();
+    library.add_data(Path::new("b.l6t"), &patch(131098, "Me", "")).unwrap();
+    library.add_data(Path::new("c.l6t"), &patch(131104, "You", "Metal")).unwrap();
+
+    let report = library_report(&library.entries);
+    assert_eq!(report.patches, 3);
+    assert_eq!(report.authors, vec![
+        Count { value: "Me".into(), count: 2 },
+        Count { value: "You".into(), count: 1 }
+    ]);
+    assert_eq!(report.styles.len(), 2);
+
+    let [device] = &report.devices[..] else { panic!("expected a single device") };
+    assert_eq!(device.device, "PODxt");
+    let amps = device.models.iter().find(|d| d.name == "Amp").unwrap();
+    assert_eq!(amps.counts, vec![
+        Count { value: "Plexi 45".into(), count: 2 },
+        Count { value: "Brit J-800".into(), count: 1 }
+    ]);
+    assert!(device.params.iter().any(|d| d.name == "amp_select" && d.counts.len() == 2));
+    assert!(!device.untouched.contains(&"amp_select".to_string()));
+    assert!(device.untouched.contains(&"drive".to_string()));
+
+    let csv = report.to_csv();
+    assert!(csv.starts_with("section,device,name,value,count\n"));
+    assert!(csv.contains("model,PODxt,Amp,Plexi 45,2\n"));
+    assert!(csv.contains("style,,,\"Rock, Blues\",1\n"));
+}
@@ -1,6 +1,8 @@
 use std::collections::HashMap;
 use std::fmt::{Display, Formatter};
+use thiserror::Error;
 use crate::group::ValueGroup;
+use crate::model::{DataModel, ParamType};
 use crate::value::{Value, ValueMap, ValueType};
 #[cfg(feature = "serde")]
 use serde_map_to_array::HashMapToArray;
@@ -14,6 +16,7 @@ pub enum FormattingType {
         &'static HashMap<u32, String>
     ),
     FloatLookup(
+        #[cfg_attr(feature = "serde", serde(serialize_with = "serialize_float_lookup"))]
         &'static Vec<(u32, String)>
     ),
     Percent,
@@ -23,7 +26,23 @@ pub enum FormattingType {
     BPM,
 }
 
-#[derive(Clone)]
+/// Serialize float lookup entries the same way `HashMapToArray`
+/// serializes int lookups: as an array of `{ "key": .., "value": .. }`
+#[cfg(feature = "serde")]
+fn serialize_float_lookup<S: serde::Serializer>(vec: &&'static Vec<(u32, String)>, serializer: S) -> Result<S::Ok, S::Error> {
+    use serde::ser::SerializeSeq;
+
+    #[derive(serde::Serialize)]
+    struct Entry<'a> { key: u32, value: &'a String }
+
+    let mut seq = serializer.serialize_seq(Some(vec.len()))?;
+    for (key, value) in vec.iter() {
+        seq.serialize_element(&Entry { key: *key, value })?;
+    }
+    seq.end()
+}
+
+#[derive(Clone, Debug)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct Range {
     pub min: f32,
@@ -48,6 +67,211 @@ pub struct  ValueInfo {
 
 pub type ValueInfoMap = HashMap<String, ValueInfo>;
 
+impl Conversion {
+    // Conversion done in f64 because we also put u32 through the same conversion
+    pub fn convert(&self, value: f64) -> f64 {
+        match self {
+            Conversion::Linear { k, a, b } => {
+                (*k as f64) * ((*a as f64) + value) + (*b as f64)
+            }
+            Conversion::Interpolate { points } => {
+                let mut val = 0.0;
+                for w in points.windows(2) {
+                    let (x1, y1) = w[0];
+                    let (x2, y2) = w[1];
+                    let x1 = x1 as f64;
+                    let x2 = x2 as f64;
+                    let y1 = y1 as f64;
+                    let y2 = y2 as f64;
+                    if value > x2 { continue }
+                    val = y1 + (value - x1) * (y2 - y1) / (x2 - x1);
+                    break;
+                }
+                val
+            }
+        }
+    }
+
+    /// Inverse of `convert`. Interpolation points must be monotone for the
+    /// inverse to exist, values outside of the interpolated range have no
+    /// inverse and result in `None`.
+    pub fn invert(&self, value: f64) -> Option<f64> {
+        match self {
+            Conversion::Linear { k, a, b } => {
+                if *k == 0.0 { return None }
+                Some((value - (*b as f64)) / (*k as f64) - (*a as f64))
+            }
+            Conversion::Interpolate { points } => {
+                for w in points.windows(2) {
+                    let (x1, y1) = w[0];
+                    let (x2, y2) = w[1];
+                    let x1 = x1 as f64;
+                    let x2 = x2 as f64;
+                    let y1 = y1 as f64;
+                    let y2 = y2 as f64;
+                    if value < y1.min(y2) || value > y1.max(y2) { continue }
+                    if y1 == y2 { return Some(x1) }
+                    return Some(x1 + (value - y1) * (x2 - x1) / (y2 - y1));
+                }
+                None
+            }
+        }
+    }
+}
+
+impl ValueInfo {
+    /// Unit of the displayed value, `None` for unitless values
+    pub fn unit(&self) -> Option<&'static str> {
+        match self.formatting_type {
+            FormattingType::Percent => Some("%"),
+            FormattingType::Millis(_) => Some("ms"),
+            FormattingType::Hertz => Some("Hz"),
+            FormattingType::Decibel(_) => Some("dB"),
+            FormattingType::BPM => Some("BPM"),
+            _ => None
+        }
+    }
+
+    /// Convert a simple value to the units this value info describes,
+    /// `None` for values that cannot be converted (bools, strings)
+    pub fn convert(&self, value: &Value) -> Option<f64> {
+        let value = match value {
+            Value::Int(v) => *v as f64,
+            Value::Float(v) => *v as f64,
+            _ => return None
+        };
+        let value = match &self.conversion {
+            None => value,
+            Some(conversion) => conversion.convert(value)
+        };
+        Some(value)
+    }
+
+    /// Parse a value as displayed by `RichValue` (e.g. "350 ms", "70 %",
+    /// "-6.0 dB" or "Plexi 45") back into a simple value of the given type.
+    /// Unit suffixes are optional, lookup names are matched case-insensitively.
+    pub fn parse(&self, input: &str, param_type: &ParamType) -> Result<Value, ParseError> {
+        let input = input.trim();
+        let number = |suffix: &str| -> Result<f64, ParseError> {
+            let s = strip_suffix_ignore_case(input, suffix).trim();
+            s.parse::<f64>().map_err(|_| ParseError::InvalidNumber(input.to_string()))
+        };
+
+        let converted = match self.formatting_type {
+            FormattingType::Simple => {
+                return parse_simple(input, param_type);
+            }
+            FormattingType::Percent => number("%")? / 100.0,
+            FormattingType::Millis(_) => number("ms")?,
+            FormattingType::Hertz => number("Hz")?,
+            FormattingType::Decibel(_) => number("dB")?,
+            FormattingType::BPM => number("BPM")?,
+            FormattingType::IntLookup(map) => {
+                let found = map.iter()
+                    .find(|(_, n)| n.eq_ignore_ascii_case(input))
+                    .map(|(k, _)| *k as f64);
+                match found {
+                    Some(v) => v,
+                    None => input.parse::<u32>()
+                        .map_err(|_| ParseError::UnknownName(input.to_string()))? as f64
+                }
+            }
+            FormattingType::FloatLookup(map) => {
+                let pos = map.iter()
+                    .position(|(_, n)| n.eq_ignore_ascii_case(input))
+                    .ok_or_else(|| ParseError::UnknownName(input.to_string()))?;
+                float_lookup_value(map, pos)
+            }
+        };
+
+        self.unconvert(converted, param_type)
+            .map_err(|e| match e {
+                ParseError::NoInverse(_) | ParseError::InvalidNumber(_) =>
+                    ParseError::NoInverse(input.to_string()),
+                e => e
+            })
+    }
+
+    /// Inverse of `convert`: turn a value in the units this value info
+    /// describes back into a simple value of the given type
+    pub fn unconvert(&self, converted: f64, param_type: &ParamType) -> Result<Value, ParseError> {
+        let raw = match &self.conversion {
+            None => converted,
+            Some(conversion) => conversion.invert(converted)
+                .ok_or_else(|| ParseError::NoInverse(converted.to_string()))?
+        };
+
+        match param_type {
+            ParamType::Int => {
+                let v = raw.round();
+                if v < 0.0 || v > u32::MAX as f64 {
+                    return Err(ParseError::InvalidNumber(converted.to_string()));
+                }
+                Ok(Value::Int(v as u32))
+            }
+            ParamType::Float => Ok(Value::Float(raw as f32)),
+            ParamType::Bool => Err(ParseError::InvalidType(converted.to_string(), param_type.clone()))
+        }
+    }
+}
+
+/// Converted value for the float lookup entry at `pos`. Aims for the middle
+/// of the range the entry covers so that rounding doesn't tip the value into
+/// a neighbouring entry.
+pub(crate) fn float_lookup_value(map: &[(u32, String)], pos: usize) -> f64 {
+    let start = map[pos].0 as f64;
+    match map.get(pos + 1) {
+        Some((end, _)) => (start + *end as f64) / 2.0,
+        None => start + 0.5
+    }
+}
+
+#[derive(Error, Debug)]
+pub enum ParseError {
+    #[error("Invalid number \"{0}\"")]
+    InvalidNumber(String),
+    #[error("Unknown name \"{0}\"")]
+    UnknownName(String),
+    #[error("Value \"{0}\" cannot be converted back")]
+    NoInverse(String),
+    #[error("Value \"{0}\" cannot be parsed as {1:?}")]
+    InvalidType(String, ParamType),
+    #[error("Value \"{0}\" not found in data model")]
+    UnknownParam(String),
+}
+
+fn strip_suffix_ignore_case<'a>(input: &'a str, suffix: &str) -> &'a str {
+    let n = input.len().saturating_sub(suffix.len());
+    match input.get(n..) {
+        Some(end) if end.eq_ignore_ascii_case(suffix) => &input[..n],
+        _ => input
+    }
+}
+
+fn parse_simple(input: &str, param_type: &ParamType) -> Result<Value, ParseError> {
+    let invalid = || ParseError::InvalidType(input.to_string(), param_type.clone());
+    match param_type {
+        ParamType::Int => input.parse::<u32>().map(Value::Int).map_err(|_| invalid()),
+        ParamType::Float => input.parse::<f32>().map(Value::Float).map_err(|_| invalid()),
+        ParamType::Bool => match input.to_ascii_lowercase().as_str() {
+            "true" | "on" | "1" => Ok(Value::Bool(true)),
+            "false" | "off" | "0" => Ok(Value::Bool(false)),
+            _ => Err(invalid())
+        }
+    }
+}
+
+/// Parse a human-readable value of the named param of the data model
+pub fn parse_value(name: &str, input: &str, model: &DataModel) -> Result<Value, ParseError> {
+    let param = model.find_param(name)
+        .ok_or_else(|| ParseError::UnknownParam(name.to_string()))?;
+    let param_type = param.get_type();
+    match model.info_map.get(name) {
+        Some(info) => info.parse(input, &param_type),
+        None => parse_simple(input.trim(), &param_type)
+    }
+}
+
 #[derive(Clone)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub struct RichValue {
@@ -66,28 +290,10 @@ impl RichValue {
         self.value.get_type()
     }
 
-    // Conversion done in f64 because we also put u32 through the same conversion
     fn convert_value(&self, value: f64) -> f64 {
         match &self.conversion {
             None => { value }
-            Some(Conversion::Linear { k, a, b }) => {
-                (*k as f64) * ((*a as f64) + value) + (*b as f64)
-            }
-            Some(Conversion::Interpolate { points }) => {
-                let mut val = 0.0;
-                for w in points.windows(2) {
-                    let (x1, y1) = w[0];
-                    let (x2, y2) = w[1];
-                    let x1 = x1 as f64;
-                    let x2 = x2 as f64;
-                    let y1 = y1 as f64;
-                    let y2 = y2 as f64;
-                    if value > x2 { continue }
-                    val = y1 + (value - x1) * (y2 - y1) / (x2 - x1);
-                    break;
-                }
-                val
-            }
+            Some(conversion) => { conversion.convert(value) }
         }
     }
 
@@ -0,0 +1,125 @@
+//! JSON Schema export of data models.
+//!
+//! The schema describes a `ValueMap` for the device: every named param
+//! is a property with its JSON type, the range of the raw values and
+//! allowed choices, with the English label and description of the param
+//! as "title" and "description". Display information that JSON Schema has no words
+//! for is put in "x-" keywords: "x-unit", "x-range" (range in display
+//! units, percent as a fraction) and "x-conversion" (raw value to
+//! display units).
+use serde_json::{json, Map, Value as JsonValue};
+use crate::model::{DataModel, Param, ParamType};
+use crate::label::{description, label};
+use crate::rich::ValueInfo;
+use crate::value::Value;
+
+fn json_type(param_type: &ParamType) -> &'static str {
+    match param_type {
+        ParamType::Int => "integer",
+        ParamType::Float => "number",
+        ParamType::Bool => "boolean"
+    }
+}
+
+fn json_value(value: &Value) -> JsonValue {
+    match value {
+        Value::Bool(v) => json!(v),
+        Value::Int(v) => json!(v),
+        Value::Float(v) => json!(v),
+        Value::String(v) => json!(v),
+    }
+}
+
+/// Raw value range for the display range of the value info, if the
+/// conversion can be inverted
+fn raw_range(info: &ValueInfo, param_type: &ParamType) -> Option<(JsonValue, JsonValue)> {
+    let range = info.range.as_ref()?;
+    if !range.min.is_finite() || !range.max.is_finite() {
+        return None;
+    }
+    let min = info.unconvert(range.min as f64, param_type).ok()?;
+    let max = info.unconvert(range.max as f64, param_type).ok()?;
+    let (min, max) = (json_value(&min), json_value(&max));
+    // conversions may be decreasing
+    match (min.as_f64(), max.as_f64()) {
+        (Some(a), Some(b)) if a > b => Some((max, min)),
+        _ => Some((min, max))
+    }
+}
+
+fn param_schema(model: &DataModel, name: &str, param: &Param) -> JsonValue {
+    let param_type = param.get_type();
+    let mut schema = Map::new();
+    schema.insert("type".into(), json!(json_type(&param_type)));
+    schema.insert("title".into(), json!(label(name, "en")));
+    if let Some(description) = description(name, "en") {
+        schema.insert("description".into(), json!(description));
+    }
+
+    if let Param::Derived { .. } = param {
+        schema.insert("readOnly".into(), json!(true));
+    }
+
+    if let Param::FixedParam { .. } = param {
+        // fixed params select slots, the allowed values are all
+        // the values the slots are selected by
+        let mut values = model.groups.iter()
+            .flat_map(|g| &g.slots)
+            .flat_map(|s| &s.params)
+            .filter_map(|p| match p {
+                Param::FixedParam { name: n, param_value, .. } if n == name => Some(*param_value),
+                _ => None
+            })
+            .collect::<Vec<_>>();
+        values.sort();
+        values.dedup();
+        schema.insert("enum".into(), json!(values));
+    }
+
+    if let Some(choices) = model.choices(name) {
+        let one_of = choices.iter()
+            .map(|c| json!({ "const": json_value(&c.value), "title": c.label }))
+            .collect::<Vec<_>>();
+        schema.remove("enum");
+        schema.insert("oneOf".into(), json!(one_of));
+    }
+
+    if let Some(info) = model.info_map.get(name) {
+        if let Some(unit) = info.unit() {
+            schema.insert("x-unit".into(), json!(unit));
+        }
+        if let Some(range) = &info.range {
+            let bound = |v: f32| if v.is_finite() { json!(v) } else { JsonValue::Null };
+            schema.insert("x-range".into(), json!({ "min": bound(range.min), "max": bound(range.max) }));
+            if let Some((min, max)) = raw_range(info, &param_type) {
+                schema.insert("minimum".into(), min);
+                schema.insert("maximum".into(), max);
+            }
+        }
+        if let Some(conversion) = &info.conversion {
+            schema.insert("x-conversion".into(), json!(conversion));
+        }
+    }
+
+    JsonValue::Object(schema)
+}
+
+/// JSON Schema describing the values of the data model
+pub fn json_schema(model: &DataModel, title: &str) -> JsonValue {
+    let mut properties = Map::new();
+    let params = model.groups.iter()
+        .flat_map(|g| &g.slots)
+        .flat_map(|s| &s.params);
+    for param in params {
+        let Some(name) = param.get_name() else { continue };
+        if properties.contains_key(name) { continue }
+        properties.insert(name.clone(), param_schema(model, name, param));
+    }
+
+    json!({
+        "$schema": "https://json-schema.org/draft/2020-12/schema",
+        "title": title,
+        "type": "object",
+        "properties": properties
+    })
+}
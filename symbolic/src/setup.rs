@@ -0,0 +1,73 @@
+//! Amp setups and fx setups.
+//!
+//! Line6 Edit can save just the amp or just the effects of a patch as an
+//! amp setup (L6AS) or an fx setup (L6FS) file. These are patches with
+//! only part of the models: an amp setup holds the models of the amp
+//! section (amp, cab, EQ, etc.), an fx setup holds the models before and
+//! after the amp. Models outside of these sections, e.g. the misc slot
+//! with the tempo, stay with the full patch.
+use thiserror::Error;
+use file::model::{L6Patch, Model, PatchType};
+use crate::chain::Section;
+
+#[cfg(test)]
+mod tests;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum SetupError {
+    #[error("Not an amp setup or fx setup")]
+    NotASetup,
+    #[error("Setup is for device {found:#x}, the patch is for device {expected:#x}")]
+    DeviceMismatch { expected: u32, found: u32 }
+}
+
+/// Whether a setup of the type carries the model in the slot
+pub fn in_setup(patch_type: PatchType, slot_id: u32) -> bool {
+    matches!((patch_type, Section::from_slot_id(slot_id)),
+        (PatchType::AmpSetup, Some(Section::Amp)) |
+        (PatchType::FxSetup, Some(Section::PreAmp | Section::PostAmp)))
+}
+
+/// Amp setup or fx setup of a full patch
+pub fn extract_setup(patch: &L6Patch, patch_type: PatchType) -> Result<L6Patch, SetupError> {
+    if patch_type == PatchType::Patch {
+        return Err(SetupError::NotASetup);
+    }
+    let models = patch.models.iter()
+        .filter(|m| in_setup(patch_type, m.slot_id))
+        .cloned()
+        .collect();
+
+    Ok(L6Patch {
+        patch_type,
+        target_device: patch.target_device.clone(),
+        models,
+        meta: patch.meta.clone()
+    })
+}
+
+/// Replace the models of the patch in the sections of the amp setup or
+/// fx setup with the models of the setup. The name and metadata of the
+/// patch are kept.
+pub fn apply_setup(patch: &mut L6Patch, setup: &L6Patch) -> Result<(), SetupError> {
+    if setup.patch_type == PatchType::Patch {
+        return Err(SetupError::NotASetup);
+    }
+    let (expected, found) = (patch.target_device.midi_id, setup.target_device.midi_id);
+    if expected != found {
+        return Err(SetupError::DeviceMismatch { expected, found });
+    }
+
+    // setup models go where the first replaced model was
+    let position = patch.models.iter()
+        .position(|m| in_setup(setup.patch_type, m.slot_id))
+        .unwrap_or(patch.models.len());
+    patch.models.retain(|m| !in_setup(setup.patch_type, m.slot_id));
+    let models = setup.models.iter()
+        .filter(|m| in_setup(setup.patch_type, m.slot_id))
+        .cloned()
+        .collect::<Vec<Model>>();
+    patch.models.splice(position .. position, models);
+
+    Ok(())
+}
@@ -0,0 +1,42 @@
+use file::decoder::{Decoder, DecoderResult};
+use file::encoder::Encoder;
+use file::model::{MetaTags, PatchType};
+use crate::data::data_model_by_id;
+use crate::setup::{apply_setup, extract_setup, SetupError};
+use crate::value::{new_patch, read_values, write_values, Value};
+
+#[test]
+fn test_setups() {
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let mut values = podxt.default_values();
+    values.insert("delay_enable".into(), Value::Bool(true));
+    let mut patch = write_values(&values, podxt, MetaTags::default()).unwrap();
+    patch.target_device.name = "Full".into();
+
+    let mut other = values.clone();
+    other.insert("amp_select".into(), Value::Int(131104)); // Brit J-800
+    other.insert("delay_enable".into(), Value::Bool(false));
+    let source = write_values(&other, podxt, MetaTags::default()).unwrap();
+
+    let amp = extract_setup(&source, PatchType::AmpSetup).unwrap();
+    assert!(amp.models.iter().all(|m| m.slot_id >> 16 == 3));
+    let data = Encoder::write(&amp).unwrap();
+    let DecoderResult::Patch(amp) = Decoder::read(&data).unwrap() else { panic!("expected a patch") };
+    assert_eq!(amp.patch_type, PatchType::AmpSetup);
+
+    apply_setup(&mut patch, &amp).unwrap();
+    let (read, diagnostics) = read_values(&patch, podxt);
+    assert_eq!(diagnostics, vec![]);
+    assert_eq!(read["amp_select"], Value::Int(131104));
+    assert_eq!(read["delay_enable"], Value::Bool(true));
+    assert_eq!(patch.target_device.name, "Full");
+
+    let fx = extract_setup(&source, PatchType::FxSetup).unwrap();
+    apply_setup(&mut patch, &fx).unwrap();
+    let (read, _) = read_values(&patch, podxt);
+    assert_eq!(read["delay_enable"], Value::Bool(false));
+
+    assert_eq!(apply_setup(&mut patch, &source), Err(SetupError::NotASetup));
+    let pod2 = extract_setup(&new_patch(0x000300).unwrap(), PatchType::AmpSetup).unwrap();
+    assert!(matches!(apply_setup(&mut patch, &pod2), Err(SetupError::DeviceMismatch { .. })));
+}
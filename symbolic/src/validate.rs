@@ -0,0 +1,97 @@
+use thiserror::Error;
+use crate::model::{DataModel, ParamType};
+use crate::rich::{FormattingType, Range};
+use crate::value::{Value, ValueMap, ValueType};
+
+/// A single problem found with a value when checking it against a data model
+#[derive(Error, Debug)]
+pub enum Violation {
+    #[error("Value \"{name}\" not found in data model")]
+    UnknownParam { name: String },
+    #[error("Invalid value type {value_type} for \"{name}\", expected {param_type:?}")]
+    InvalidType { name: String, param_type: ParamType, value_type: ValueType },
+    #[error("Value {value} of \"{name}\" out of range {}..{}", range.min, range.max)]
+    OutOfRange { name: String, value: f64, range: Range },
+    #[error("Value {value} of \"{name}\" not found in lookup")]
+    NotInLookup { name: String, value: Value },
+}
+
+impl Violation {
+    pub fn name(&self) -> &str {
+        match self {
+            Violation::UnknownParam { name } => name,
+            Violation::InvalidType { name, .. } => name,
+            Violation::OutOfRange { name, .. } => name,
+            Violation::NotInLookup { name, .. } => name,
+        }
+    }
+}
+
+fn type_matches(value: &Value, param_type: &ParamType) -> bool {
+    matches!((value, param_type),
+        (Value::Int(_), ParamType::Int) |
+        (Value::Float(_), ParamType::Float) |
+        (Value::Bool(_), ParamType::Bool))
+}
+
+/// Check every value in the map against the data model: the value must
+/// belong to a known param, have the type of the param, after conversion
+/// fall into the range of the param and, for lookup params, be one of the
+/// known lookup entries. Returns all violations found, ordered by value
+/// name, so an empty vector means the values are valid.
+pub fn validate_values(values: &ValueMap, model: &DataModel) -> Vec<Violation> {
+    let mut names = values.keys().collect::<Vec<_>>();
+    names.sort();
+
+    let mut violations = vec![];
+    for name in names {
+        let value = values.get(name).unwrap();
+        let Some(param) = model.find_param(name) else {
+            violations.push(Violation::UnknownParam { name: name.clone() });
+            continue;
+        };
+
+        let param_type = param.get_type();
+        if !type_matches(value, &param_type) {
+            violations.push(Violation::InvalidType {
+                name: name.clone(),
+                param_type,
+                value_type: value.get_type()
+            });
+            continue;
+        }
+
+        let Some(info) = model.info_map.get(name) else { continue };
+        let Some(converted) = info.convert(value) else { continue };
+
+        if let Some(range) = &info.range {
+            let in_range = (range.min as f64) <= converted && converted <= (range.max as f64);
+            if !in_range {
+                violations.push(Violation::OutOfRange {
+                    name: name.clone(),
+                    value: converted,
+                    range: range.clone()
+                });
+                continue;
+            }
+        }
+
+        let in_lookup = match (&info.formatting_type, value) {
+            (FormattingType::IntLookup(map), Value::Int(_)) => {
+                map.contains_key(&(converted as u32))
+            }
+            (FormattingType::FloatLookup(map), Value::Float(_)) => {
+                converted >= 0.0 && map.first().is_some_and(|(k, _)| *k <= converted as u32)
+            }
+            _ => true
+        };
+        if !in_lookup {
+            violations.push(Violation::NotInLookup {
+                name: name.clone(),
+                value: value.clone()
+            });
+        }
+    }
+
+    violations
+}
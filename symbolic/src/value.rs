@@ -1,10 +1,13 @@
 use std::collections::HashMap;
-use std::default;
-use std::fmt::{Display, Formatter, LowerHex};
-use file::model::{L6Patch, Model, ModelParam, Value as L6Value};
-use crate::model::{DataModel, Param, ParamType, Slot};
+use std::fmt::{Display, Formatter};
+use file::model::{L6Patch, MetaTags, Model, ModelParam, TargetDevice, Value as L6Value};
+use crate::data::{data_model_id, data_model_info_by_id};
+use crate::model::{DataModel, Derive, Param, ParamType, Slot};
 
-#[derive(Clone)]
+#[cfg(test)]
+mod tests;
+
+#[derive(Clone, Debug, PartialEq)]
 #[cfg_attr(feature = "serde", derive(serde::Serialize))]
 pub enum Value {
     Bool(bool),
@@ -63,6 +66,7 @@ impl Value {
     }
 }
 
+#[derive(Debug)]
 pub enum ValueType {
     Bool,
     Int,
@@ -95,10 +99,63 @@ impl Value {
 
 pub type ValueMap = HashMap<String, Value>;
 
-pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<String>) {
+/// A problem found reading the values of a patch
+#[derive(thiserror::Error, Clone, Debug, PartialEq)]
+pub enum Diagnostic {
+    #[error("Slot {slot_id:#04x} model={model_id:#08x} ordinal={ordinal} missing param {param_id:#x} ({param_type:?})")]
+    MissingParam { slot_id: u32, model_id: u32, ordinal: u8, param_id: u32, param_type: ParamType },
+    #[error("Slot {slot_id:#04x} model={model_id:#08x} ordinal={ordinal} param {param_id:#x} has invalid format, expected {param_type:?}")]
+    InvalidParamType { slot_id: u32, model_id: u32, ordinal: u8, param_id: u32, param_type: ParamType },
+    #[error("Slot {slot_id:#04x} model={model_id:#08x} ordinal={ordinal} unprocessed param {param_id:#x}")]
+    UnprocessedParam { slot_id: u32, model_id: u32, ordinal: u8, param_id: u32 },
+    #[error("Slot {slot_id:#04x} model={model_id:#08x} ordinal={ordinal} unprocessed")]
+    UnprocessedModel { slot_id: u32, model_id: u32, ordinal: u8 },
+}
+
+impl Diagnostic {
+    pub fn slot_id(&self) -> u32 {
+        match self {
+            Diagnostic::MissingParam { slot_id, .. } |
+            Diagnostic::InvalidParamType { slot_id, .. } |
+            Diagnostic::UnprocessedParam { slot_id, .. } |
+            Diagnostic::UnprocessedModel { slot_id, .. } => *slot_id
+        }
+    }
+
+    pub fn model_id(&self) -> u32 {
+        match self {
+            Diagnostic::MissingParam { model_id, .. } |
+            Diagnostic::InvalidParamType { model_id, .. } |
+            Diagnostic::UnprocessedParam { model_id, .. } |
+            Diagnostic::UnprocessedModel { model_id, .. } => *model_id
+        }
+    }
+
+    pub fn ordinal(&self) -> u8 {
+        match self {
+            Diagnostic::MissingParam { ordinal, .. } |
+            Diagnostic::InvalidParamType { ordinal, .. } |
+            Diagnostic::UnprocessedParam { ordinal, .. } |
+            Diagnostic::UnprocessedModel { ordinal, .. } => *ordinal
+        }
+    }
+
+    /// Param id, `None` for unprocessed models
+    pub fn param_id(&self) -> Option<u32> {
+        match self {
+            Diagnostic::MissingParam { param_id, .. } |
+            Diagnostic::InvalidParamType { param_id, .. } |
+            Diagnostic::UnprocessedParam { param_id, .. } => Some(*param_id),
+            Diagnostic::UnprocessedModel { .. } => None
+        }
+    }
+}
+
+pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<Diagnostic>) {
     let mut data: HashMap<String, Value> = HashMap::new();
     let mut processed_models = vec![];
-    let mut errors = vec![];
+    let mut derived = vec![];
+    let mut diagnostics = vec![];
 
     let slots = model.groups.iter().flat_map(|g| &g.slots);
     for slot in slots {
@@ -123,7 +180,7 @@ pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<String>
                     let patch_param = patch_model.params.iter()
                         .find(|p| p.param_id == *param_id);
                     let Some(patch_param) = patch_param else {
-                        missing_params.push(*param_id);
+                        missing_params.push((*param_id, param_type.clone()));
                         continue;
                     };
                     processed_params.push(*param_id);
@@ -131,7 +188,7 @@ pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<String>
                     if let Ok(value) = value {
                         (name, value)
                     } else {
-                        invalid_params.push(*param_id);
+                        invalid_params.push((*param_id, param_type.clone()));
                         continue
                     }
                 }
@@ -150,6 +207,11 @@ pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<String>
                     processed_params.push(*param_id);
                     continue;
                 }
+                Param::Derived { name, derive } => {
+                    // computed once all other values are known
+                    derived.push((name, derive));
+                    continue;
+                }
             };
 
             data.insert(name.clone(), value);
@@ -159,154 +221,338 @@ pub fn read_values(patch: &L6Patch, model: &DataModel) -> (ValueMap, Vec<String>
             .filter(|v| !processed_params.contains(v))
             .collect::<Vec<_>>();
 
-        let model = patch_model; // for error reporting below
         processed_models.push(patch_model);
-        if !missing_params.is_empty() {
-            errors.push(
-                format!("Slot {:#04x} model={:#08x} ordinal={} missing params: {}",
-                        model.slot_id, model.model_id, model.ordinal,
-                        mk_hex_el_string(missing_params, ", ")
-                )
-            )
+        let (slot_id, model_id, ordinal) = (patch_model.slot_id, patch_model.model_id, patch_model.ordinal);
+        for (param_id, param_type) in missing_params {
+            diagnostics.push(Diagnostic::MissingParam { slot_id, model_id, ordinal, param_id, param_type });
         }
-        if !invalid_params.is_empty() {
-            errors.push(
-                format!("Slot {:#04x} model={:#08x} ordinal={} params with invalid format: {}",
-                        model.slot_id, model.model_id, model.ordinal,
-                        mk_hex_el_string(invalid_params, ", ")
-                )
-            )
+        for (param_id, param_type) in invalid_params {
+            diagnostics.push(Diagnostic::InvalidParamType { slot_id, model_id, ordinal, param_id, param_type });
         }
-        if !unprocessed_params.is_empty() {
-            errors.push(
-                format!("Slot {:#04x} model={:#08x} ordinal={} unprocessed params: {}",
-                        model.slot_id, model.model_id, model.ordinal,
-                        mk_hex_el_string(unprocessed_params, ", ")
-                )
-            )
+        for param_id in unprocessed_params {
+            diagnostics.push(Diagnostic::UnprocessedParam { slot_id, model_id, ordinal, param_id });
         }
     }
 
-    let unprocessed_models = patch.models.iter().filter(|m| !processed_models.contains(m));
-    for model in unprocessed_models {
-        errors.push(
-            format!("Slot {:#04x} model={:#08x} ordinal={} unprocessed",
-                    model.slot_id, model.model_id, model.ordinal
-            )
-        )
+    let unprocessed_models = patch.models.iter()
+        .filter(|m| !processed_models.iter().any(|p| std::ptr::eq(*p, *m)));
+    for m in unprocessed_models {
+        diagnostics.push(Diagnostic::UnprocessedModel {
+            slot_id: m.slot_id, model_id: m.model_id, ordinal: m.ordinal
+        });
+    }
+
+    for (name, derive) in derived {
+        if let Some(value) = derive_value(derive, &data, model) {
+            data.insert(name.clone(), value);
+        }
     }
 
-    (data, errors)
+    (data, diagnostics)
 }
 
-pub fn write_values(values: ValueMap, model: &DataModel) -> L6Patch {
-    let floats_as_ints = model.floats_as_ints;
-    let mut models: Vec<Model> = vec![];
+fn derive_value(derive: &Derive, values: &ValueMap, model: &DataModel) -> Option<Value> {
+    let converted = |name: &String| {
+        let value = values.get(name)?;
+        match model.info_map.get(name) {
+            Some(info) => info.convert(value),
+            None => match value {
+                Value::Int(v) => Some(*v as f64),
+                Value::Float(v) => Some(*v as f64),
+                _ => None
+            }
+        }
+    };
+    let synced_beats = |tempo: &String, note: &String, beats: &HashMap<u32, f32>| {
+        let Some(Value::Int(note)) = values.get(note) else { return None };
+        let beats = *beats.get(note)? as f64;
+        let tempo = converted(tempo)?;
+        if tempo <= 0.0 { return None }
+        Some((beats, tempo))
+    };
+
+    let value = match derive {
+        Derive::SyncedTime { tempo, note, time, beats } => {
+            match synced_beats(tempo, note, beats) {
+                Some((beats, tempo)) => beats * 60000.0 / tempo,
+                None => converted(time)?
+            }
+        }
+        Derive::SyncedRate { tempo, note, rate, beats } => {
+            match synced_beats(tempo, note, beats) {
+                Some((beats, tempo)) => tempo / 60.0 / beats,
+                None => converted(rate)?
+            }
+        }
+    };
 
-    let slots = model.groups.iter().flat_map(|g| &g.slots);
-    for slot in slots {
-        let fixed_param = slot.params.iter().find(|p| match p {
-                    Param::FixedParam { name, param_value, param_type, .. } => {
-                        let Some(v) = values.get(name) else {
-                            return false;
-                        };
-                        // TODO: dubious conversion to u32
-                        if *param_type != ParamType::Int { todo!() }
-                        let Ok(v): Result<u32, _> = v.try_into() else {
-                            return false;
-                        };
-
-                        v == *param_value
-                    }
-                    _ => false
-                });
+    Some(Value::Float(value as f32))
+}
 
-        let slot_id = slot.fixed_slot.or(
-            fixed_param.and_then(|p| match p {
-                Param::FixedParam { slot_id, .. } => slot_id.clone(),
-                _ => None
-            })
-        );
-        let model = slot.fixed_model.or_else(|| {
-           slot.params.iter().find_map(|p| {
-               match p {
-                   Param::SlotModel { name, .. } => {
-                       let Some(v) = values.get(name) else {
-                           return None;
-                       };
-                       let v: Option<u32> = v.try_into().ok();
-                       v
-                   }
-                   _ => None
-               }
-           })
-        });
-        let enable = slot.fixed_enable.or_else(|| {
-            slot.params.iter().find_map(|p| {
-                match p {
-                    Param::SlotEnable { name } => {
-                        let Some(v) = values.get(name) else {
-                            return None;
-                        };
-                        let v: Option<bool> = v.try_into().ok();
-                        v
-                    }
-                    _ => None
-                }
-            })
-        });
+#[derive(thiserror::Error, Debug)]
+pub enum WriteError {
+    #[error("No value {name:?} for param {param_id:#x} for slot {slot_id:#x}")]
+    MissingValue { name: String, param_id: u32, slot_id: u32 },
 
-        let found_by_fixed_int = fixed_param.is_some();
-        let have_all = slot_id.is_some() && model.is_some();
-        let slot_found = found_by_fixed_int || have_all;
-        if !slot_found {
-            continue;
+    #[error("Value {name:?} of type {value_type} cannot be written as {param_type:?}")]
+    InvalidValueType { name: String, param_type: ParamType, value_type: ValueType },
+
+    #[error("Missing slot id or model for slot (slot={slot_id:x?}, model={model_id:x?})")]
+    MissingSlotOrModel { slot_id: Option<u32>, model_id: Option<u32> },
+
+    #[error("Fixed param {name:?} of type {param_type:?} is not supported")]
+    UnsupportedFixedParam { name: String, param_type: ParamType },
+
+    #[error("Data model is not registered for any device")]
+    UnknownDataModel
+}
+
+/// A patch model slot resolved from the values
+pub(crate) struct ResolvedSlot {
+    pub slot_id: u32,
+    pub model_id: u32,
+    pub enabled: bool
+}
+
+/// Find out, whether the slot is selected by the values and, if so,
+/// what slot id, model id and enable state the patch model should have.
+///
+/// A slot with fixed params is selected when the values match one of
+/// the fixed params' alternatives for every fixed param name, e.g. both
+/// `mod_position` and `mod_select` for the modulation slots of the PODxt;
+/// matching any one name would select every slot of the position. Other
+/// slots are selected when both the slot id and the model id are known.
+/// Only fixed params of type Int are supported, others are an error when
+/// there is a value for them.
+pub(crate) fn resolve_slot(slot: &Slot, values: &ValueMap) -> Result<Option<ResolvedSlot>, WriteError> {
+    let mut fixed_names: Vec<&String> = vec![];
+    let mut fixed_slot_id = None;
+    for param in &slot.params {
+        let Param::FixedParam { name, .. } = param else { continue };
+        if fixed_names.contains(&name) { continue }
+        fixed_names.push(name);
+
+        let Some(value) = values.get(name) else {
+            return Ok(None);
+        };
+        let mut matched = false;
+        for p in &slot.params {
+            let Param::FixedParam { name: n, param_value, param_type, slot_id } = p else { continue };
+            if n != name { continue }
+            if *param_type != ParamType::Int {
+                return Err(WriteError::UnsupportedFixedParam {
+                    name: name.clone(), param_type: param_type.clone()
+                });
+            }
+            // TODO: dubious conversion to u32
+            let Ok(v): Result<u32, _> = value.try_into() else {
+                return Ok(None);
+            };
+            if v == *param_value {
+                fixed_slot_id = fixed_slot_id.or(*slot_id);
+                matched = true;
+                break;
+            }
         }
-        if !have_all {
-            panic!("Missing slot id or model");
+        if !matched {
+            return Ok(None);
         }
+    }
 
-        let mut params = vec![];
-        for param in &slot.params {
-            match param {
-                Param::IgnoreParam { param_id, param_type } => {
-                    // put a "0" for ignored parameters
-                    let zero = match param_type {
-                        ParamType::Int | ParamType::Bool => L6Value::Int(0),
-                        ParamType::Float => L6Value::Float(0.0)
-                    };
-                    params.push(
-                        ModelParam { param_id: *param_id, value: zero }
-                    )
+    let slot_id = slot.fixed_slot.or(fixed_slot_id);
+    let model_id = slot.fixed_model.or_else(|| {
+        slot.params.iter().find_map(|p| match p {
+            Param::SlotModel { name } => values.get(name).and_then(|v| v.try_into().ok()),
+            _ => None
+        })
+    });
+    let enabled = slot.fixed_enable.or_else(|| {
+        slot.params.iter().find_map(|p| match p {
+            Param::SlotEnable { name } => values.get(name).and_then(|v| v.try_into().ok()),
+            _ => None
+        })
+    });
 
-                }
-                Param::Param { name, param_id, param_type } => {
-                    let Some(value) = values.get(name) else {
-                        panic!("No value {:?} for param {:#x} for slot {:#x}", name, param_id, slot_id.unwrap());
-                    };
-                    let value = value_to_l6(value, param_type, floats_as_ints);
-                    params.push(
-                        ModelParam { param_id: *param_id, value }
-                    )
-                }
-                _ => {}
+    let found_by_fixed_param = !fixed_names.is_empty();
+    let have_all = slot_id.is_some() && model_id.is_some();
+    if !found_by_fixed_param && !have_all {
+        return Ok(None);
+    }
+    let (Some(slot_id), Some(model_id)) = (slot_id, model_id) else {
+        return Err(WriteError::MissingSlotOrModel { slot_id, model_id });
+    };
+
+    Ok(Some(ResolvedSlot { slot_id, model_id, enabled: enabled.unwrap_or(false) }))
+}
+
+/// Create the patch model params for a slot from the values
+pub(crate) fn write_slot_params(slot: &Slot, slot_id: u32, values: &ValueMap, floats_as_ints: bool) -> Result<Vec<ModelParam>, WriteError> {
+    let mut params = vec![];
+    for param in &slot.params {
+        match param {
+            Param::IgnoreParam { param_id, param_type } => {
+                // put a "0" for ignored parameters
+                let zero = match param_type {
+                    ParamType::Int | ParamType::Bool => L6Value::Int(0),
+                    ParamType::Float => L6Value::Float(0.0)
+                };
+                params.push(
+                    ModelParam { param_id: *param_id, value: zero }
+                )
             }
+            Param::Param { name, param_id, param_type } => {
+                let Some(value) = values.get(name) else {
+                    return Err(WriteError::MissingValue {
+                        name: name.clone(), param_id: *param_id, slot_id
+                    });
+                };
+                let value = value_to_l6(name, value, param_type, floats_as_ints)?;
+                params.push(
+                    ModelParam { param_id: *param_id, value }
+                )
+            }
+            _ => {}
         }
+    }
+
+    Ok(params)
+}
+
+pub fn write_values(values: &ValueMap, model: &DataModel, meta: MetaTags) -> Result<L6Patch, WriteError> {
+    let Some(midi_id) = data_model_id(model) else {
+        return Err(WriteError::UnknownDataModel);
+    };
+    let floats_as_ints = model.floats_as_ints;
+    let mut models: Vec<Model> = vec![];
+
+    let slots = model.groups.iter().flat_map(|g| &g.slots);
+    for slot in slots {
+        let Some(resolved) = resolve_slot(slot, values)? else {
+            continue;
+        };
+        let params = write_slot_params(slot, resolved.slot_id, values, floats_as_ints)?;
         models.push(
             Model {
-                model_id: model.unwrap(),
-                slot_id: slot_id.unwrap(),
-                enabled: enable.unwrap_or(false),
+                model_id: resolved.model_id,
+                slot_id: resolved.slot_id,
+                enabled: resolved.enabled,
                 ordinal: 0,
                 params,
             }
         );
     }
 
-    L6Patch {
+    // The target device name is left empty: it is for the caller to fill in
+    let target_device = TargetDevice { midi_id, ..Default::default() };
+    Ok(L6Patch {
+        target_device,
         models,
-        ..default::Default::default()
+        meta,
+        ..Default::default()
+    })
+}
+
+/// A complete patch for the device with the default values of its
+/// data model, see `DataModel::default_value`
+pub fn new_patch(device_id: u32) -> Result<L6Patch, WriteError> {
+    let info = data_model_info_by_id(device_id).ok_or(WriteError::UnknownDataModel)?;
+    let mut patch = write_values(&info.model.default_values(), info.model, MetaTags::default())?;
+    patch.target_device = TargetDevice { midi_id: device_id, name: info.name.into(), version: 0 };
+
+    Ok(patch)
+}
+
+/// Apply the values onto an existing patch in place. Only the params
+/// whose values differ from the ones read from the patch are updated,
+/// everything else (model ordinals, unknown params, ignored params) is
+/// left exactly as read. The values may contain only a portion of the
+/// params, the rest are assumed unchanged.
+///
+/// When a changed value selects a different slot (a different stomp
+/// model, a different effect position, etc.), the patch model of the
+/// no longer selected slot is replaced with a new one, keeping its
+/// ordinal. In this case the values must contain all the params of
+/// the newly selected slot.
+pub fn apply_values(patch: &mut L6Patch, values: &ValueMap, model: &DataModel) -> Result<(), WriteError> {
+    let (mut merged, _) = read_values(patch, model);
+    let changed = values.iter()
+        .filter(|(name, value)| merged.get(*name) != Some(value))
+        .map(|(name, _)| name)
+        .collect::<Vec<_>>();
+    if changed.is_empty() {
+        return Ok(());
+    }
+    merged.extend(values.iter().map(|(k, v)| (k.clone(), v.clone())));
+
+    for group in &model.groups {
+        for slot in &group.slots {
+            let slot_changed = slot.params.iter()
+                .filter_map(|p| p.get_name())
+                .any(|name| changed.contains(&name));
+            if !slot_changed { continue }
+
+            let Some(resolved) = resolve_slot(slot, &merged)? else {
+                continue;
+            };
+
+            let patch_model = patch.models.iter_mut()
+                .find(|m| model_matches_slot(m, slot));
+            if let Some(patch_model) = patch_model {
+                // Same slot, update the changed params in place
+                let has_enable = slot.fixed_enable.is_some() ||
+                    slot.params.iter().any(|p| matches!(p, Param::SlotEnable { .. }));
+                patch_model.slot_id = resolved.slot_id;
+                patch_model.model_id = resolved.model_id;
+                if has_enable {
+                    patch_model.enabled = resolved.enabled;
+                }
+
+                for param in &slot.params {
+                    let Param::Param { name, param_id, param_type } = param else { continue };
+                    if !changed.contains(&name) { continue }
+
+                    let value = value_to_l6(name, &merged[name], param_type, model.floats_as_ints)?;
+                    match patch_model.params.iter_mut().find(|p| p.param_id == *param_id) {
+                        Some(p) => p.value = value,
+                        None => patch_model.params.push(ModelParam { param_id: *param_id, value })
+                    }
+                }
+                continue;
+            }
+
+            // A newly selected slot, replace the patch model of a slot
+            // from the same group that is no longer selected
+            let params = write_slot_params(slot, resolved.slot_id, &merged, model.floats_as_ints)?;
+            let mut new_model = Model {
+                model_id: resolved.model_id,
+                slot_id: resolved.slot_id,
+                enabled: resolved.enabled,
+                ordinal: 0,
+                params,
+            };
+
+            let mut replaced = None;
+            for (n, m) in patch.models.iter().enumerate() {
+                for s in &group.slots {
+                    if model_matches_slot(m, s) && resolve_slot(s, &merged)?.is_none() {
+                        replaced = Some(n);
+                    }
+                }
+                if replaced.is_some() { break }
+            }
+            match replaced {
+                Some(n) => {
+                    new_model.ordinal = patch.models[n].ordinal;
+                    patch.models[n] = new_model;
+                }
+                None => {
+                    patch.models.push(new_model);
+                }
+            }
+        }
     }
+
+    Ok(())
 }
 
 fn value_from_l6(value: &L6Value, param_type: &ParamType, floats_as_ints: bool) -> Result<Value, String> {
@@ -353,43 +599,20 @@ fn value_from_l6(value: &L6Value, param_type: &ParamType, floats_as_ints: bool)
     }
 }
 
-fn value_to_l6(value: &Value, param_type: &ParamType, floats_as_ints: bool) -> L6Value {
-    match param_type {
-        ParamType::Int => {
-            match value {
-                Value::Int(v) => { L6Value::Int(*v) }
-                _ => {
-                    panic!("Int value expected")
-                }
-            }
-        }
-        ParamType::Float => {
-            match value {
-                Value::Float(v) if floats_as_ints => {
-                    L6Value::Int(v.to_bits())
-                }
-                Value::Float(v) => {
-                    L6Value::Float(*v)
-                }
-                _ => {
-                    panic!("Float value expected")
-                }
-            }
-        }
-        ParamType::Bool => {
-            match value {
-                Value::Bool(v) if !v => {
-                    L6Value::Int(0)
-                }
-                Value::Bool(v) if *v => {
-                    L6Value::Int(1)
-                }
-                _ => {
-                    panic!("Bool value expected")
-                }
-            }
+fn value_to_l6(name: &str, value: &Value, param_type: &ParamType, floats_as_ints: bool) -> Result<L6Value, WriteError> {
+    let value = match (param_type, value) {
+        (ParamType::Int, Value::Int(v)) => L6Value::Int(*v),
+        (ParamType::Float, Value::Float(v)) if floats_as_ints => L6Value::Int(v.to_bits()),
+        (ParamType::Float, Value::Float(v)) => L6Value::Float(*v),
+        (ParamType::Bool, Value::Bool(v)) => L6Value::Int(if *v { 1 } else { 0 }),
+        _ => {
+            return Err(WriteError::InvalidValueType {
+                name: name.into(), param_type: param_type.clone(), value_type: value.get_type()
+            });
         }
-    }
+    };
+
+    Ok(value)
 }
 
 pub(crate) fn model_matches_slot(model: &Model, slot: &Slot) -> bool {
@@ -405,8 +628,3 @@ pub(crate) fn model_matches_slot(model: &Model, slot: &Slot) -> bool {
 
     slot_matched && model_matched && enable_matched
 }
-
-fn mk_hex_el_string<T: LowerHex>(vec: Vec<T>, separator: &str) -> String {
-    vec.iter().map(|v| format!("{:#x}", v))
-        .collect::<Vec<_>>().join(separator)
-}
\ No newline at end of file
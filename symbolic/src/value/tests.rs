@@ -0,0 +1,400 @@
+use file::decoder::{Decoder, DecoderResult};
+use file::encoder::Encoder;
+use file::model::{L6Patch, MetaTags, Model, ModelParam, Value as L6Value};
+use file::random::{random_meta, random_patch, Rng};
+
+use crate::chain::{signal_chain, Section};
+use crate::data::{data_model_by_id, data_model_info_by_id};
+use crate::detect::{detect_data_model, select_data_model};
+use crate::data::data_models;
+use crate::pack::{availability, pack_uses, required_packs};
+use crate::random::random_values;
+use crate::model::{DataModel, ModelPack, Param, ParamType};
+use crate::rich::{enrich_values, parse_value, ParseError};
+use crate::validate::{validate_values, Violation};
+use crate::value::{apply_values, new_patch, read_values, write_values, Diagnostic, Value, ValueMap};
+
+/// Values for every named param of the data model, selecting the first
+/// alternative of every fixed param
+fn all_values(model: &DataModel) -> ValueMap {
+    let mut values = ValueMap::new();
+    for param in model.groups.iter().flat_map(|g| &g.slots).flat_map(|s| &s.params) {
+        let (name, value) = match param {
+            Param::SlotModel { name } => (name, Value::Int(0)),
+            Param::SlotEnable { name } => (name, Value::Bool(true)),
+            Param::Param { name, param_type: ParamType::Int, .. } => (name, Value::Int(0)),
+            Param::Param { name, param_type: ParamType::Float, .. } => (name, Value::Float(0.5)),
+            Param::Param { name, param_type: ParamType::Bool, .. } => (name, Value::Bool(false)),
+            Param::FixedParam { name, param_value, .. } => (name, Value::Int(*param_value)),
+            _ => continue
+        };
+        values.entry(name.clone()).or_insert(value);
+    }
+
+    values
+}
+
+#[test]
+fn test_apply_values() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let mut values = all_values(model);
+    values.insert("amp_select".into(), Value::Int(131098)); // Plexi 45
+    values.insert("cab_select".into(), Value::Int(16908305)); // 4x12 Line 6
+    values.insert("wah_select".into(), Value::Int(0x2060000));
+
+    let mut patch = write_values(&values, model, MetaTags::default()).unwrap();
+    for (n, m) in patch.models.iter_mut().enumerate() {
+        m.ordinal = n as u8;
+    }
+    let amp = patch.models.iter_mut().find(|m| m.slot_id == 0x30000).unwrap();
+    amp.params.push(ModelParam { param_id: 0x123456, value: L6Value::Int(42) });
+    let eq = patch.models.iter_mut().find(|m| m.slot_id == 0x30004).unwrap();
+    eq.params.iter_mut().find(|p| p.param_id == 0x100008).unwrap().value = L6Value::Int(7);
+
+    // change one knob
+    let changes = ValueMap::from([ ("drive".into(), Value::Float(0.75)) ]);
+    apply_values(&mut patch, &changes, model).unwrap();
+
+    let amp = patch.models.iter().find(|m| m.slot_id == 0x30000).unwrap();
+    assert_eq!(amp.ordinal, 1);
+    assert!(matches!(amp.params.iter().find(|p| p.param_id == 0x100003).unwrap().value, L6Value::Float(v) if v == 0.75));
+    assert!(matches!(amp.params.iter().find(|p| p.param_id == 0x123456).unwrap().value, L6Value::Int(42)));
+    let eq = patch.models.iter().find(|m| m.slot_id == 0x30004).unwrap();
+    assert!(matches!(eq.params.iter().find(|p| p.param_id == 0x100008).unwrap().value, L6Value::Int(7)));
+
+    // select a different stomp, move modulation post-amp
+    let stomp_ordinal = patch.models.iter().find(|m| m.slot_id == 0x20003).unwrap().ordinal;
+    let changes = ValueMap::from([
+        ("stomp_select".into(), Value::Int(5)), // Blue Comp
+        ("stomp_sustain".into(), Value::Float(0.25)),
+        ("stomp_level".into(), Value::Float(0.5)),
+        ("mod_position".into(), Value::Int(1)),
+    ]);
+    let models = patch.models.len();
+    apply_values(&mut patch, &changes, model).unwrap();
+    assert_eq!(patch.models.len(), models);
+
+    let stomp = patch.models.iter().find(|m| m.slot_id == 0x20003).unwrap();
+    assert_eq!(stomp.model_id, 0x2000002);
+    assert_eq!(stomp.ordinal, stomp_ordinal);
+    assert!(patch.models.iter().any(|m| m.slot_id == 0x50003));
+    assert!(!patch.models.iter().any(|m| m.slot_id == 0x20004));
+
+    let (read, errors) = read_values(&patch, model);
+    assert!(errors.iter().all(|e| matches!(e, Diagnostic::UnprocessedParam { param_id: 0x123456, .. })), "{:?}", errors);
+    assert_eq!(read["stomp_select"], Value::Int(5));
+    assert_eq!(read["stomp_sustain"], Value::Float(0.25));
+    assert_eq!(read["mod_position"], Value::Int(1));
+    assert_eq!(read["drive"], Value::Float(0.75));
+}
+
+#[test]
+fn test_write_values() {
+    use std::collections::HashMap;
+    use crate::data::register_data_model;
+    use crate::data::shorthand::{fixed_int, fixed_int_for_slot_id, float, int};
+    use crate::model::{Group, Slot};
+    use crate::value::WriteError;
+
+    // fx slots are selected by both position and model
+    let fx = |select: u32, model_id: u32| Slot {
+        fixed_slot: None,
+        fixed_model: Some(model_id),
+        fixed_enable: Some(true),
+        params: vec![
+            fixed_int_for_slot_id("fx_position", 0, 0x20000),
+            fixed_int_for_slot_id("fx_position", 1, 0x50000),
+            fixed_int("fx_select", select),
+            float(0x10, "fx_mix")
+        ]
+    };
+    let odd = Slot {
+        fixed_slot: Some(0x10000),
+        fixed_model: Some(0x1),
+        fixed_enable: None,
+        params: vec![
+            Param::FixedParam { name: "odd".into(), param_value: 0, param_type: ParamType::Float, slot_id: None },
+            int(0x20, "odd_level")
+        ]
+    };
+    let model = DataModel {
+        floats_as_ints: false,
+        groups: vec![Group { name: "Fx".into(), slots: vec![fx(0, 0x100), fx(1, 0x200), odd] }],
+        info_map: HashMap::new(),
+        packs: HashMap::new(),
+        defaults: ValueMap::new()
+    };
+    let model = register_data_model(0x7f0010, "Write test", model).unwrap();
+
+    let values = ValueMap::from([
+        ("fx_position".into(), Value::Int(1)),
+        ("fx_select".into(), Value::Int(1)),
+        ("fx_mix".into(), Value::Float(0.25))
+    ]);
+    let meta = MetaTags { author: "Me".into(), ..Default::default() };
+    let patch = write_values(&values, model, meta).unwrap();
+    assert_eq!(patch.target_device.midi_id, 0x7f0010);
+    assert_eq!(patch.meta.author, "Me");
+    assert_eq!(patch.models.len(), 1);
+    assert_eq!((patch.models[0].slot_id, patch.models[0].model_id), (0x50000, 0x200));
+
+    let mut missing = values.clone();
+    missing.remove("fx_mix");
+    assert!(matches!(write_values(&missing, model, MetaTags::default()),
+                     Err(WriteError::MissingValue { name, param_id: 0x10, slot_id: 0x50000 }) if name == "fx_mix"));
+
+    let mut invalid = values.clone();
+    invalid.insert("fx_mix".into(), Value::Bool(true));
+    assert!(matches!(write_values(&invalid, model, MetaTags::default()),
+                     Err(WriteError::InvalidValueType { name, param_type: ParamType::Float, .. }) if name == "fx_mix"));
+
+    let mut odd = values.clone();
+    odd.insert("odd".into(), Value::Float(0.0));
+    odd.insert("odd_level".into(), Value::Int(1));
+    assert!(matches!(write_values(&odd, model, MetaTags::default()),
+                     Err(WriteError::UnsupportedFixedParam { name, param_type: ParamType::Float }) if name == "odd"));
+}
+
+#[test]
+fn test_validate_values() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let values = ValueMap::from([
+        ("amp_select".into(), Value::Int(131098)), // Plexi 45
+        ("drive".into(), Value::Float(0.5)),
+        ("comp_gain".into(), Value::Float(0.5)),
+    ]);
+    assert!(validate_values(&values, model).is_empty());
+
+    let values = ValueMap::from([
+        ("amp_select".into(), Value::Int(12345)),
+        ("drive".into(), Value::Int(1)),
+        ("comp_gain".into(), Value::Float(1.5)),
+        ("no_such_param".into(), Value::Bool(true)),
+    ]);
+    let violations = validate_values(&values, model);
+    assert_eq!(violations.len(), 4);
+    assert!(matches!(&violations[0], Violation::NotInLookup { name, .. } if name == "amp_select"));
+    assert!(matches!(&violations[1], Violation::OutOfRange { name, value, .. } if name == "comp_gain" && *value == 24.0));
+    assert!(matches!(&violations[2], Violation::InvalidType { name, .. } if name == "drive"));
+    assert!(matches!(&violations[3], Violation::UnknownParam { name } if name == "no_such_param"));
+}
+
+#[test]
+fn test_parse_value() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let values = ValueMap::from([
+        ("amp_select".into(), Value::Int(131098)),
+        ("drive".into(), Value::Float(0.7)),
+        ("delay_time".into(), Value::Float(0.25)),
+        ("eq_1_gain".into(), Value::Float(0.25)),
+        ("eq_2_freq".into(), Value::Float(0.3)),
+        ("tempo".into(), Value::Float(120.0)),
+        ("delay_bits".into(), Value::Float(0.6)),
+        ("amp_enable".into(), Value::Bool(true)),
+    ]);
+    let rich = enrich_values(values.clone(), &model.info_map);
+    for (name, value) in rich.iter() {
+        let display = value.to_string();
+        let parsed = parse_value(name, &display, model)
+            .unwrap_or_else(|e| panic!("{}: {}", name, e));
+        let redisplay = enrich_values(ValueMap::from([(name.clone(), parsed)]), &model.info_map)
+            .remove(name).unwrap().to_string();
+        assert_eq!(display, redisplay, "{}", name);
+    }
+
+    assert_eq!(parse_value("amp_select", "plexi 45", model).unwrap(), Value::Int(131098));
+    assert_eq!(parse_value("eq_2_freq", "450 Hz", model).unwrap(), Value::Float(48.0 / 127.0));
+    assert_eq!(parse_value("drive", "50", model).unwrap(), Value::Float(0.5));
+    assert!(matches!(parse_value("amp_select", "Plexi 46", model), Err(ParseError::UnknownName(_))));
+    assert!(matches!(parse_value("drive", "loud", model), Err(ParseError::InvalidNumber(_))));
+}
+
+#[test]
+fn test_derived_synced_values() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let mut values = all_values(model);
+    values.insert("tempo".into(), Value::Float(120.0));
+    values.insert("delay_time".into(), Value::Float(0.0));
+    values.insert("delay_note_select".into(), Value::Int(6)); // quarter
+    values.insert("mod_note_select".into(), Value::Int(9)); // eighth
+
+    let patch = write_values(&values, model, MetaTags::default()).unwrap();
+    let (read, _) = read_values(&patch, model);
+    assert_eq!(read["delay_effective_time"], Value::Float(500.0));
+    assert_eq!(read["mod_effective_speed"], Value::Float(4.0));
+
+    values.insert("delay_note_select".into(), Value::Int(0)); // off
+    let patch = write_values(&values, model, MetaTags::default()).unwrap();
+    let (read, _) = read_values(&patch, model);
+    assert_eq!(read["delay_effective_time"], Value::Float(20.0));
+}
+
+#[test]
+fn test_read_diagnostics() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let mut patch = write_values(&all_values(model), model, MetaTags::default()).unwrap();
+    let (_, diagnostics) = read_values(&patch, model);
+    assert_eq!(diagnostics, vec![]);
+
+    let amp = patch.models.iter_mut().find(|m| m.slot_id == 0x30000).unwrap();
+    let (slot_id, model_id, ordinal) = (amp.slot_id, amp.model_id, amp.ordinal);
+    let float_param = amp.params.iter_mut().find(|p| matches!(p.value, L6Value::Float(_))).unwrap();
+    float_param.value = L6Value::Int(7);
+    let invalid_id = float_param.param_id;
+    let missing_id = amp.params.iter().rev().find(|p| matches!(p.value, L6Value::Float(_))).unwrap().param_id;
+    amp.params.retain(|p| p.param_id != missing_id);
+    amp.params.push(ModelParam { param_id: 0x123456, value: L6Value::Int(1) });
+    patch.models.push(Model { model_id: 1, slot_id: 0x7777, enabled: true, ordinal: 0, params: vec![] });
+
+    let (_, diagnostics) = read_values(&patch, model);
+    assert_eq!(diagnostics, vec![
+        Diagnostic::MissingParam { slot_id, model_id, ordinal, param_id: missing_id, param_type: ParamType::Float },
+        Diagnostic::InvalidParamType { slot_id, model_id, ordinal, param_id: invalid_id, param_type: ParamType::Float },
+        Diagnostic::UnprocessedParam { slot_id, model_id, ordinal, param_id: 0x123456 },
+        Diagnostic::UnprocessedModel { slot_id: 0x7777, model_id: 1, ordinal: 0 },
+    ]);
+    assert_eq!(diagnostics[2].param_id(), Some(0x123456));
+    assert_eq!(diagnostics[3].param_id(), None);
+}
+
+#[test]
+fn test_detect_data_model() {
+    let model = data_model_by_id(0x030006).unwrap(); // Bass PODxt
+    let mut patch = write_values(&all_values(model), model, MetaTags::default()).unwrap();
+
+    let candidates = detect_data_model(&patch);
+    assert_eq!(candidates[0].id, 0x030006);
+    assert_eq!(candidates[0].confidence, 1.0);
+    assert!(candidates.iter().all(|c| c.id != 0x000200));
+
+    // unknown and wrong device ids
+    for id in [0x123456, 0x000300] {
+        patch.target_device.midi_id = id;
+        let candidate = select_data_model(&patch).unwrap();
+        assert!(std::ptr::eq(candidate.model, model));
+    }
+
+    // device variants that fit equally well keep the header id
+    patch.target_device.midi_id = 0x030007; // Bass PODxt Pro
+    let candidate = select_data_model(&patch).unwrap();
+    assert_eq!(candidate.id, 0x030007);
+}
+
+#[test]
+fn test_signal_chain() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let mut values = all_values(model);
+    values.insert("amp_select".into(), Value::Int(131098)); // Plexi 45
+    values.insert("mod_position".into(), Value::Int(1));
+    values.insert("delay_position".into(), Value::Int(0));
+    values.insert("reverb_position".into(), Value::Int(1));
+    values.insert("gate_enable".into(), Value::Bool(false));
+
+    let patch = write_values(&values, model, MetaTags::default()).unwrap();
+    let chain = signal_chain(&patch, model);
+    let names = chain.iter().map(|b| b.name.as_str()).collect::<Vec<_>>();
+    assert_eq!(names, vec![
+        "Noise gate", "Volume pedal", "Wah pedal", "Stomp", "Delay",
+        "Amp", "Cab", "EQ",
+        "Compressor", "Modulation", "Reverb"
+    ]);
+
+    let gate = &chain[0];
+    assert_eq!(gate.section, Section::PreAmp);
+    assert!(!gate.enabled);
+    let amp = chain.iter().find(|b| b.name == "Amp").unwrap();
+    assert_eq!(amp.section, Section::Amp);
+    assert_eq!(amp.model_name.as_deref(), Some("Plexi 45"));
+    let delay = chain.iter().find(|b| b.name == "Delay").unwrap();
+    assert_eq!(delay.slot_id, 0x20005);
+    let modulation = chain.iter().find(|b| b.name == "Modulation").unwrap();
+    assert_eq!((modulation.slot_id, modulation.section), (0x50003, Section::PostAmp));
+}
+
+#[test]
+fn test_required_packs() {
+    let model = data_model_by_id(0x030002).unwrap();
+    let mut values = all_values(model);
+    values.insert("amp_select".into(), Value::Int(131098)); // Plexi 45
+    let patch = write_values(&values, model, MetaTags::default()).unwrap();
+    assert_eq!(required_packs(&patch, model), vec![]);
+
+    values.insert("amp_select".into(), Value::Int(131119)); // MS-Bomber Uber
+    values.insert("stomp_select".into(), Value::Int(10)); // FX-Killer Z
+    let patch = write_values(&values, model, MetaTags::default()).unwrap();
+    assert_eq!(required_packs(&patch, model), vec![ModelPack::MetalShop, ModelPack::FxJunkie]);
+    let uses = pack_uses(&patch, model);
+    assert_eq!(uses[0].name, "amp_select");
+    assert_eq!(uses[0].label.as_deref(), Some("MS-Bomber Uber"));
+
+    // "BX" amps are stock on Bass PODxt
+    let bass = data_model_by_id(0x030006).unwrap();
+    assert_eq!(bass.pack("amp_select", 196609), None);
+    let devices = availability("amp_select", 196609).into_iter()
+        .filter(|a| a.id < 0x7f0000)
+        .map(|a| (a.id, a.pack))
+        .collect::<Vec<_>>();
+    assert!(devices.contains(&(0x030002, Some(ModelPack::BassExpansion))));
+    assert!(devices.contains(&(0x030006, None)));
+    assert!(!devices.iter().any(|(id, _)| *id == 0x000300));
+}
+
+#[test]
+fn test_new_patch() {
+    for id in [0x000300, 0x000200, 0x030002, 0x030005, 0x03000a, 0x030006, 0x030007, 0x03000b, 0x000500, 0x000600] {
+        let info = data_model_info_by_id(id).unwrap();
+        let patch = new_patch(id).unwrap();
+        assert_eq!(patch.target_device.midi_id, id);
+        assert_eq!(patch.target_device.name, info.name);
+
+        let (values, diagnostics) = read_values(&patch, info.model);
+        assert_eq!(diagnostics, vec![], "{}", info.name);
+        let violations = validate_values(&values, info.model);
+        assert!(violations.is_empty(), "{}: {:?}", info.name, violations);
+    }
+
+    let podxt = data_model_by_id(0x030002).unwrap();
+    let (values, _) = read_values(&new_patch(0x030002).unwrap(), podxt);
+    assert_eq!(values["amp_select"], Value::Int(131098)); // Plexi 45
+    assert_eq!(values["amp_enable"], Value::Bool(true));
+    assert_eq!(values["delay_enable"], Value::Bool(false));
+    assert_eq!(values["mod_position"], Value::Int(0));
+    assert!(new_patch(0x123456).is_err());
+}
+
+fn round_trip(patch: &L6Patch) -> L6Patch {
+    let data = Encoder::write(patch).unwrap();
+    match Decoder::read(&data).unwrap() {
+        DecoderResult::Patch(p) => p,
+        DecoderResult::Bundle(_) => panic!("expected a patch")
+    }
+}
+
+#[test]
+fn test_random_round_trip() {
+    let devices = data_models().into_iter()
+        .filter(|(id, info)| info.alias_of.is_none() && *id < 0x7f0000)
+        .collect::<Vec<_>>();
+    for (id, info) in devices {
+        for seed in 0 .. 20 {
+            let mut rng = Rng::new(seed);
+            let values = random_values(info.model, &mut rng);
+            let violations = validate_values(&values, info.model);
+            assert!(violations.is_empty(), "{} #{}: {:?}", info.name, seed, violations);
+
+            let mut patch = write_values(&values, info.model, random_meta(&mut rng)).unwrap();
+            patch.target_device.midi_id = id;
+            // the device name of PINF is limited to 15 characters
+            patch.target_device.name = info.name.chars().take(15).collect();
+            let (read, diagnostics) = read_values(&patch, info.model);
+            assert_eq!(diagnostics, vec![], "{} #{}", info.name, seed);
+            for (name, value) in &values {
+                assert_eq!(read.get(name), Some(value), "{} #{}: {}", info.name, seed, name);
+            }
+            assert_eq!(round_trip(&patch), patch, "{} #{}", info.name, seed);
+
+            let patch = random_patch(&mut rng, id);
+            assert_eq!(round_trip(&patch), patch, "{} #{}", info.name, seed);
+        }
+    }
+}
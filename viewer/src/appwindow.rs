@@ -230,7 +230,10 @@ mod imp {
                         );
 
                         for patch in &bank.patches {
-                            let name = &patch.patch.target_device.name;
+                            let name = match &patch.program {
+                                Some(program) => format!("{}  {}", program, patch.patch.target_device.name),
+                                None => patch.patch.target_device.name.clone()
+                            };
                             m.insert_with_values(
                                 Some(&bank_iter), None,
                                 &[(0, &0), (1, &name)]
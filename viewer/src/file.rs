@@ -1,11 +1,17 @@
 use std::cell::Ref;
 use l6t::model::L6Patch;
+use l6t::symbolic::chain::Block;
 use l6t::symbolic::rich::RichValueGroup;
+use l6t::symbolic::value::Diagnostic;
 
 pub struct Patch {
     pub patch: L6Patch,
     pub values: Vec<RichValueGroup>,
-    pub errors: Vec<String>
+    pub chain: Vec<Block>,
+    pub errors: Vec<String>,
+    pub diagnostics: Vec<Diagnostic>,
+    /// Device program of a patch of a bundle, e.g. "12C"
+    pub program: Option<String>
 }
 
 pub struct Bank {
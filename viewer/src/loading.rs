@@ -5,10 +5,14 @@ use gtk4::prelude::FileExt;
 use log::warn;
 use l6t::decoder::{Decoder, DecoderResult};
 use l6t::model::L6Patch;
+use l6t::symbolic::bundle::bundle_capacity;
+use l6t::symbolic::chain::signal_chain;
 use l6t::symbolic::data::data_model_by_id;
+use l6t::symbolic::detect::select_data_model;
 use l6t::symbolic::group::group_values;
+use l6t::symbolic::pack::required_packs;
 use l6t::symbolic::rich::enrich_values;
-use l6t::symbolic::value::read_values;
+use l6t::symbolic::value::{read_values, Diagnostic};
 use crate::file::{Bank, Bundle, File, Patch};
 
 pub fn load_file(file: gio::File) -> Result<File> {
@@ -16,15 +20,30 @@ pub fn load_file(file: gio::File) -> Result<File> {
         .context("Read failed")?;
 
     let process_patch = |patch: L6Patch| {
-        let Some(model) = data_model_by_id(patch.target_device.midi_id) else {
-            let error = format!("Model not found: {:04x?}", patch.target_device.midi_id);
-            return Patch { patch, values: Default::default(), errors: vec![error] }
+        let id = patch.target_device.midi_id;
+        let Some(candidate) = select_data_model(&patch) else {
+            let error = format!("Model not found: {:04x?}", id);
+            return Patch { patch, values: Default::default(), chain: vec![], errors: vec![error], diagnostics: vec![], program: None }
         };
-        let (values, errors) = read_values(&patch, &model);
+        let model = candidate.model;
+        let (values, diagnostics) = read_values(&patch, model);
+        let mut errors = vec![];
+        if !data_model_by_id(id).is_some_and(|m| std::ptr::eq(m, model)) {
+            let error = format!("Model {:04x?} detected for device id {:04x?} ({:.0} % confidence)",
+                                candidate.id, id, candidate.confidence * 100.0);
+            errors.push(error);
+        }
+        for pack in required_packs(&patch, model) {
+            errors.push(format!("Needs the {} model pack", pack));
+        }
+        for d in diagnostics.iter().filter(|d| matches!(d, Diagnostic::InvalidParamType { .. })) {
+            warn!("{}", d);
+        }
         let values = enrich_values(values, &model.info_map);
         let values = group_values(&patch, &values, model);
+        let chain = signal_chain(&patch, model);
 
-        Patch { patch, values, errors }
+        Patch { patch, values, chain, errors, diagnostics, program: None }
     };
 
     let contents = match Decoder::read(&data)? {
@@ -32,11 +51,17 @@ pub fn load_file(file: gio::File) -> Result<File> {
             File::Patch(process_patch(p))
         }
         DecoderResult::Bundle(b) => {
+            let capacity = bundle_capacity(&b);
+            // nested folders of collections are shown as banks named by path
             let banks = b.banks.into_iter()
-                .map(|b| {
+                .flat_map(|b| b.into_flat())
+                .enumerate()
+                .map(|(n, b)| {
                     let name = b.name;
                     let patches = b.patches.into_iter()
                         .map(process_patch)
+                        .enumerate()
+                        .map(|(i, p)| Patch { program: capacity.and_then(|c| c.program_name(n, i)), ..p })
                         .collect();
                     Bank { name, patches }
                 })
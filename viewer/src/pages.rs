@@ -1,3 +1,4 @@
+use l6t::symbolic::label::{description, label, system_language};
 use l6t::symbolic::value::Value;
 
 use crate::file::Patch;
@@ -33,16 +34,20 @@ pub fn patch_page(patch: &Patch) -> gtk4::Widget {
     };
     device_groups.push(device_group);
 
+    let lang = system_language();
     for g in &patch.values {
-        let mut group = model::shorthand::group(&g.name);
+        let mut group = model::shorthand::group(&label(&g.name, &lang));
 
         for (n, v) in &g.values {
             let extra = match v.get_simple() {
                 Value::Int(v) => format!(" ({:#04x})", v),
                 _ => String::new()
             };
-            let tooltip = format!("{}: {}{}", v.get_simple_type(), v.get_simple(), extra);
-            group = group.item(n, &v.to_string()).tooltip(&tooltip);
+            let mut tooltip = format!("{}\n{}: {}{}", n, v.get_simple_type(), v.get_simple(), extra);
+            if let Some(description) = description(n, &lang) {
+                tooltip = format!("{}\n{}", description, tooltip);
+            }
+            group = group.item(label(n, &lang), &v.to_string()).tooltip(&tooltip);
         }
         device_groups.push(group.into());
     }